@@ -0,0 +1,73 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::f64::consts::{PI, TAU};
+
+/// Number of times the outer arm of a run from `initial` goes over the top, in either direction,
+/// before the run settles. A run has settled once it is left with less energy than the outer arm
+/// needs to get over the top, which with damping it never gets back. Runs that haven't settled
+/// after `max_time` seconds (say because they are driven) are cut off there. Steps with the
+/// system's own solver. Only meaningful with gravity pulling down, as without it there is no top
+/// to go over.
+///
+/// ```
+/// # use nannou_test::basin::outer_flips;
+/// # use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+/// let system = DoublePendulumSystem { b: 0.5, ..Default::default() };
+/// // Too little energy to ever flip
+/// let small = DoublePendulumState::new(0.1, -0.1, 0., 0.);
+/// assert_eq!(outer_flips(&system, small, 0.01, 60.), 0);
+/// // Thrown over the top and then some
+/// let spun = DoublePendulumState::new(0., 0., 0., 30.);
+/// assert!(outer_flips(&system, spun, 0.01, 60.) > 0);
+/// ```
+pub fn outer_flips(
+    system: &DoublePendulumSystem,
+    initial: DoublePendulumState,
+    dt: f64,
+    max_time: f64,
+) -> u32 {
+    let threshold = system.potential_energy(&DoublePendulumState::new(0., PI, 0., 0.));
+    // Which turn the outer arm is on, counting from hanging down and changing over the top
+    let turn = |θ: f64| ((θ + PI) / TAU).floor();
+    let mut state = initial;
+    let mut flips = 0;
+    while state.t - initial.t < max_time && system.total_energy(&state) >= threshold {
+        let next = system.advance(state, dt);
+        if !next.is_finite() {
+            break;
+        }
+        flips += (turn(next.θ2) - turn(state.θ2)).abs() as u32;
+        state = next;
+    }
+    flips
+}
+
+/// `outer_flips` for a `cols` by `rows` grid of runs released at rest, with θ1 from -π on the
+/// left to π on the right and θ2 from π at the top to -π at the bottom, sampled at the center of
+/// each cell. Returned row by row from the top left. Runs in parallel with the `parallel`
+/// feature, with the same result either way.
+pub fn basin(
+    system: &DoublePendulumSystem,
+    cols: usize,
+    rows: usize,
+    dt: f64,
+    max_time: f64,
+) -> Vec<u32> {
+    let cell = |i: usize| {
+        let (col, row) = (i % cols, i / cols);
+        let θ1 = -PI + TAU * (col as f64 + 0.5) / cols as f64;
+        let θ2 = PI - TAU * (row as f64 + 0.5) / rows as f64;
+        outer_flips(
+            system,
+            DoublePendulumState::new(θ1, θ2, 0., 0.),
+            dt,
+            max_time,
+        )
+    };
+    #[cfg(feature = "parallel")]
+    let cells = (0..cols * rows).into_par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let cells = 0..cols * rows;
+    cells.map(cell).collect()
+}
@@ -0,0 +1,268 @@
+use crate::timeline::Param;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Options given on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    // Main window size in pixels
+    pub width: u32,
+    pub height: u32,
+    // Initial zoom in pixels per meter
+    pub scale: f64,
+    // Number of steps to integrate headlessly and time instead of opening any windows
+    pub bench: Option<usize>,
+    // Initial angles in radians and angular velocities in radians per second, overriding the
+    // config file and the defaults
+    pub theta1: Option<f64>,
+    pub theta2: Option<f64>,
+    pub omega1: Option<f64>,
+    pub omega2: Option<f64>,
+    // Length in seconds and frame rate of the GIFs recorded with Shift+V
+    pub gif_seconds: f64,
+    pub gif_fps: f64,
+    // Trajectory CSV to play back instead of simulating
+    pub play: Option<PathBuf>,
+    // Parameter to sweep from and to the given values, rendering a contact sheet of the runs
+    // instead of opening any windows
+    pub sweep: Option<(Param, f64, f64)>,
+    // Number of runs in the sweep and simulated seconds each one runs for
+    pub sweep_count: usize,
+    pub sweep_seconds: f64,
+    // Side in pixels of a basin of attraction image to render instead of opening any windows
+    pub basin: Option<u32>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            width: crate::WIDTH,
+            height: crate::HEIGHT,
+            scale: crate::LEN_SCALE,
+            bench: None,
+            theta1: None,
+            theta2: None,
+            omega1: None,
+            omega2: None,
+            gif_seconds: crate::GIF_SECONDS,
+            gif_fps: crate::GIF_FPS,
+            play: None,
+            sweep: None,
+            sweep_count: crate::SWEEP_COUNT,
+            sweep_seconds: crate::SWEEP_SECONDS,
+            basin: None,
+        }
+    }
+}
+
+pub const USAGE: &str = "usage: nannou-test [--width PIXELS] [--height PIXELS] \
+    [--scale PIXELS_PER_METER] [--bench STEPS] [--theta1-deg DEGREES] [--theta2-deg DEGREES] \
+    [--omega1 RAD_PER_S] [--omega2 RAD_PER_S] [--gif-seconds SECONDS] [--gif-fps FPS] \
+    [--play TRAJECTORY_CSV] [--sweep PARAM FROM TO] [--sweep-count RUNS] \
+    [--sweep-seconds SECONDS] [--basin PIXELS]";
+
+/// Parse the arguments after the program name, falling back to the defaults for anything not
+/// given
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--width" => parsed.width = positive(&arg, &value()?)?,
+            "--height" => parsed.height = positive(&arg, &value()?)?,
+            "--scale" => parsed.scale = positive(&arg, &value()?)?,
+            "--bench" => parsed.bench = Some(positive(&arg, &value()?)?),
+            "--theta1-deg" => parsed.theta1 = degrees(&arg, &value()?).or(parsed.theta1),
+            "--theta2-deg" => parsed.theta2 = degrees(&arg, &value()?).or(parsed.theta2),
+            "--omega1" => parsed.omega1 = finite(&arg, &value()?).or(parsed.omega1),
+            "--omega2" => parsed.omega2 = finite(&arg, &value()?).or(parsed.omega2),
+            "--gif-seconds" => parsed.gif_seconds = positive(&arg, &value()?)?,
+            "--gif-fps" => parsed.gif_fps = positive(&arg, &value()?)?,
+            "--play" => parsed.play = Some(PathBuf::from(value()?)),
+            "--sweep" => {
+                let name = value()?;
+                let which = Param::from_name(&name)
+                    .ok_or_else(|| format!("unknown parameter {} for {}", name, arg))?;
+                let from = number(&arg, &value()?)?;
+                let to = number(&arg, &value()?)?;
+                parsed.sweep = Some((which, from, to));
+            }
+            "--sweep-count" => parsed.sweep_count = positive(&arg, &value()?)?,
+            "--sweep-seconds" => parsed.sweep_seconds = positive(&arg, &value()?)?,
+            "--basin" => parsed.basin = Some(positive(&arg, &value()?)?),
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Parse an angle in degrees given for `flag` into radians, see `finite`
+fn degrees(flag: &str, value: &str) -> Option<f64> {
+    finite(flag, value).map(f64::to_radians)
+}
+
+/// Parse a finite number given for `flag`. The initial conditions are only a starting point, so
+/// instead of failing a bad one is reported and `None` returned to keep what was there before.
+fn finite(flag: &str, value: &str) -> Option<f64> {
+    match value.parse::<f64>() {
+        Ok(parsed) if parsed.is_finite() => Some(parsed),
+        _ => {
+            eprintln!("invalid value {} for {}, using the default", value, flag);
+            None
+        }
+    }
+}
+
+/// Parse a finite number given for `flag`, failing on anything else
+fn number(flag: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(parsed) if parsed.is_finite() => Ok(parsed),
+        _ => Err(format!("invalid value {} for {}", value, flag)),
+    }
+}
+
+/// Parse `value` given for `flag`, which has to be finite and greater than zero
+fn positive<T>(flag: &str, value: &str) -> Result<T, String>
+where
+    T: FromStr + PartialOrd + Default + Finite,
+    T::Err: Display,
+{
+    let parsed: T = value
+        .parse()
+        .map_err(|e| format!("invalid value {} for {}: {}", value, flag, e))?;
+    if !parsed.is_finite() {
+        Err(format!("{} must be finite, got {}", flag, value))
+    } else if parsed > T::default() {
+        Ok(parsed)
+    } else {
+        Err(format!("{} must be positive, got {}", flag, value))
+    }
+}
+
+/// Numbers `positive` parses, of which only floats can be infinite
+trait Finite {
+    fn is_finite(&self) -> bool;
+}
+
+impl Finite for f64 {
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+}
+
+impl Finite for u32 {
+    fn is_finite(&self) -> bool {
+        true
+    }
+}
+
+impl Finite for usize {
+    fn is_finite(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_give_the_defaults() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+    }
+
+    #[test]
+    fn each_flag_sets_its_option() {
+        let args = parse(&[
+            "--width",
+            "640",
+            "--height",
+            "480",
+            "--scale",
+            "50.5",
+            "--bench",
+            "1000",
+            "--omega1",
+            "-2",
+            "--omega2",
+            "3.5",
+            "--gif-seconds",
+            "4",
+            "--gif-fps",
+            "25",
+            "--play",
+            "run.csv",
+            "--sweep",
+            "g",
+            "1",
+            "20",
+            "--sweep-count",
+            "6",
+            "--sweep-seconds",
+            "12",
+            "--basin",
+            "256",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            Args {
+                width: 640,
+                height: 480,
+                scale: 50.5,
+                bench: Some(1000),
+                omega1: Some(-2.),
+                omega2: Some(3.5),
+                gif_seconds: 4.,
+                gif_fps: 25.,
+                play: Some(PathBuf::from("run.csv")),
+                sweep: Some((Param::G, 1., 20.)),
+                sweep_count: 6,
+                sweep_seconds: 12.,
+                basin: Some(256),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        assert!(parse(&["--frobnicate"]).is_err());
+        assert!(parse(&["--width"]).is_err());
+        assert!(parse(&["--width", "wide"]).is_err());
+        assert!(parse(&["--width", "0"]).is_err());
+        assert!(parse(&["--width", "-5"]).is_err());
+        assert!(parse(&["--scale", "-1.5"]).is_err());
+        assert!(parse(&["--scale", "inf"]).is_err());
+        assert!(parse(&["--gif-fps", "NaN"]).is_err());
+        assert!(parse(&["--sweep", "warp", "1", "2"]).is_err());
+        assert!(parse(&["--sweep", "g", "1", "inf"]).is_err());
+        assert!(parse(&["--sweep", "g", "1"]).is_err());
+    }
+
+    #[test]
+    fn angles_are_given_in_degrees() {
+        let args = parse(&["--theta1-deg", "90", "--theta2-deg", "-45"]).unwrap();
+        assert!((args.theta1.unwrap() - FRAC_PI_2).abs() < 1e-12);
+        assert!((args.theta2.unwrap() + FRAC_PI_4).abs() < 1e-12);
+        assert_eq!(degrees("--theta1-deg", "180"), Some(PI));
+    }
+
+    #[test]
+    fn invalid_angles_keep_the_default() {
+        assert_eq!(degrees("--theta1-deg", "north"), None);
+        assert_eq!(degrees("--theta1-deg", "inf"), None);
+        // Not an error, the angle is just left alone
+        let args = parse(&["--theta1-deg", "north", "--theta2-deg", "NaN"]).unwrap();
+        assert_eq!((args.theta1, args.theta2), (None, None));
+        let args = parse(&["--theta1-deg", "30", "--theta1-deg", "bad"]).unwrap();
+        assert!((args.theta1.unwrap() - 30f64.to_radians()).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Config file read from the working directory at startup, if there is one
+pub const PATH: &str = "pendulum.toml";
+
+/// Startup parameters and initial conditions, anything left out keeps its default
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub g: Option<f64>,
+    pub m1: Option<f64>,
+    pub m2: Option<f64>,
+    pub l1: Option<f64>,
+    pub l2: Option<f64>,
+    // Initial angles in degrees and angular velocities in radians per second, like the command
+    // line flags
+    pub theta1_deg: Option<f64>,
+    pub theta2_deg: Option<f64>,
+    pub omega1: Option<f64>,
+    pub omega2: Option<f64>,
+}
+
+impl Config {
+    /// Parse a config from the contents of a TOML file
+    pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// The config at `path`. A missing file is the same as an empty one, a malformed one is
+    /// reported on stderr and ignored.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+        Self::from_toml(&text).unwrap_or_else(|e| {
+            eprintln!("Ignoring malformed {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    /// Overwrite the parameters and initial conditions the config gives
+    pub fn apply(&self, system: &mut DoublePendulumSystem, state: &mut DoublePendulumState) {
+        let fields = [
+            (self.g, &mut system.g),
+            (self.m1, &mut system.m1),
+            (self.m2, &mut system.m2),
+            (self.l1, &mut system.l1),
+            (self.l2, &mut system.l2),
+            (self.theta1_deg.map(f64::to_radians), &mut state.θ1),
+            (self.theta2_deg.map(f64::to_radians), &mut state.θ2),
+            (self.omega1, &mut state.ω1),
+            (self.omega2, &mut state.ω2),
+        ];
+        for (value, field) in fields {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        system.sanitize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+# Moon gravity, a heavy outer bob dropped from the side
+g = 1.62
+m2 = 3
+l1 = 0.5
+theta1_deg = 90
+omega2 = -1.5
+"#;
+
+    #[test]
+    fn sample_config_deserializes() {
+        let config = Config::from_toml(SAMPLE).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                g: Some(1.62),
+                m2: Some(3.),
+                l1: Some(0.5),
+                theta1_deg: Some(90.),
+                omega2: Some(-1.5),
+                ..Config::default()
+            }
+        );
+        let mut system = DoublePendulumSystem::default();
+        let mut state = DoublePendulumState::new(0., 0.3, 2., 0.);
+        config.apply(&mut system, &mut state);
+        assert_eq!((system.g, system.m1, system.m2), (1.62, 1., 3.));
+        assert_eq!(
+            (system.l1, system.l2),
+            (0.5, DoublePendulumSystem::default().l2)
+        );
+        assert_eq!(state.θ1, std::f64::consts::FRAC_PI_2);
+        assert_eq!((state.θ2, state.ω1, state.ω2), (0.3, 2., -1.5));
+    }
+
+    #[test]
+    fn empty_config_changes_nothing() {
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn unknown_and_mistyped_fields_are_errors() {
+        assert!(Config::from_toml("gravity = 9.8").is_err());
+        assert!(Config::from_toml("g = \"strong\"").is_err());
+    }
+}
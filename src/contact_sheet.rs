@@ -0,0 +1,90 @@
+use nalgebra::Vector2;
+use nannou::image::{Rgba, RgbaImage};
+use nannou::prelude::*;
+use std::path::PathBuf;
+
+/// Side of each run's cell in pixels
+const CELL: u32 = 256;
+/// Gap between cells in pixels
+const GAP: u32 = 4;
+const BACKGROUND: Rgba<u8> = Rgba([16, 16, 20, 255]);
+
+/// Lay `paths` of the outer bob, in meters from the pivot, out in a grid as near square as
+/// possible, left to right and then top to bottom. Each is scaled so a fully stretched pendulum
+/// of `reach` meters fits its cell and drawn as a trail fading in from its start, ending in a
+/// dot at the final position.
+pub fn render(paths: &[Vec<Vector2<f64>>], reach: f64) -> RgbaImage {
+    let cols = (paths.len() as f64).sqrt().ceil().max(1.) as u32;
+    let rows = (paths.len() as u32).div_ceil(cols).max(1);
+    let mut image = RgbaImage::from_pixel(
+        cols * (CELL + GAP) + GAP,
+        rows * (CELL + GAP) + GAP,
+        BACKGROUND,
+    );
+    let scale = (CELL as f64 / 2. - 2.) / reach;
+    for (i, path) in paths.iter().enumerate() {
+        let (col, row) = (i as u32 % cols, i as u32 / cols);
+        let center = Vector2::new(
+            (GAP + col * (CELL + GAP) + CELL / 2) as f64,
+            (GAP + row * (CELL + GAP) + CELL / 2) as f64,
+        );
+        // Image rows run downwards
+        let to_pixel = |p: &Vector2<f64>| center + Vector2::new(p.x, -p.y) * scale;
+        for (j, pair) in path.windows(2).enumerate() {
+            let age = (j + 1) as f32 / path.len() as f32;
+            let c: Srgb = hsv(0.6 - 0.6 * age, 0.8, 1.).into();
+            let color = Rgba([
+                (c.red * 255.) as u8,
+                (c.green * 255.) as u8,
+                (c.blue * 255.) as u8,
+                255,
+            ]);
+            draw_line(
+                &mut image,
+                to_pixel(&pair[0]),
+                to_pixel(&pair[1]),
+                color,
+                age,
+            );
+        }
+        if let Some(last) = path.last() {
+            let p = to_pixel(last);
+            for (dx, dy) in [(0., 0.), (1., 0.), (-1., 0.), (0., 1.), (0., -1.)] {
+                blend(&mut image, p + Vector2::new(dx, dy), Rgba([255; 4]), 1.);
+            }
+        }
+    }
+    image
+}
+
+/// Path in the working directory for a sweep of `param` rendered now
+pub fn timestamped_path(param: &str) -> PathBuf {
+    PathBuf::from(format!("sweep_{}_{}.png", param, crate::unix_time()))
+}
+
+/// Mix `color` into the pixels along the segment from `a` to `b` with opacity `alpha`, a sample
+/// per pixel of length
+fn draw_line(image: &mut RgbaImage, a: Vector2<f64>, b: Vector2<f64>, color: Rgba<u8>, alpha: f32) {
+    let samples = (b - a).abs().max().ceil().max(1.) as usize;
+    for k in 0..=samples {
+        blend(
+            image,
+            a + (b - a) * (k as f64 / samples as f64),
+            color,
+            alpha,
+        );
+    }
+}
+
+/// Mix `color` into the pixel under `p` with opacity `alpha`, ignoring points off the image
+fn blend(image: &mut RgbaImage, p: Vector2<f64>, color: Rgba<u8>, alpha: f32) {
+    let (x, y) = (p.x.round(), p.y.round());
+    if x < 0. || y < 0. || x >= image.width() as f64 || y >= image.height() as f64 {
+        return;
+    }
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    for channel in 0..3 {
+        let (old, new) = (pixel.0[channel] as f32, color.0[channel] as f32);
+        pixel.0[channel] = (old + (new - old) * alpha) as u8;
+    }
+}
@@ -1,30 +1,150 @@
-use nalgebra::{vector, Const, OVector};
-use ode_solvers::{Rk4, System};
+use nalgebra::{vector, Const, Matrix2, OVector, Vector2};
+use ode_solvers::{Dopri5, Rk4, System};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::f64::consts::{PI, TAU};
+use std::fmt;
+use std::iter;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Largest internal step `DoublePendulumSystem::step` takes, longer deltas are substepped. Also
+/// sets the substeps of the symplectic solver in `advance`.
+const MAX_RK4_STEP: f64 = 1. / 240.;
+/// Default tolerance of the adaptive solver, see `DoublePendulumSystem::tol`
+const ADAPTIVE_TOLERANCE: f64 = 1e-9;
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DoublePendulumState {
-    // Top pendulum angle
+    // Top pendulum angle from hanging straight down, counter-clockwise positive
+    #[cfg_attr(feature = "serde", serde(rename = "theta1"))]
     pub θ1: f64,
-    // Lower pendulum angle
+    // Lower pendulum angle from hanging straight down, counter-clockwise positive
+    #[cfg_attr(feature = "serde", serde(rename = "theta2"))]
     pub θ2: f64,
     // Top pendulum angle change
+    #[cfg_attr(feature = "serde", serde(rename = "omega1"))]
     pub ω1: f64,
     // Lower pendulum angle change
+    #[cfg_attr(feature = "serde", serde(rename = "omega2"))]
     pub ω2: f64,
+    // Simulated time
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub t: f64,
 }
 
 impl DoublePendulumState {
     pub fn new(θ1: f64, θ2: f64, ω1: f64, ω2: f64) -> Self {
-        Self { θ1, θ2, ω1, ω2 }
+        Self {
+            θ1,
+            θ2,
+            ω1,
+            ω2,
+            t: 0.,
+        }
+    }
+
+    /// At rest with the bobs as close as the arms allow to `inner` and `outer`, given relative to
+    /// the pivot in the axes of `DoublePendulumSystem::bob_positions`. The inner bob goes where
+    /// the first arm points towards `inner`, and the outer bob where the second arm then points
+    /// towards `outer`, so unreachable positions end up at the nearest reachable ones.
+    ///
+    /// ```
+    /// use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+    /// use nalgebra::vector;
+    ///
+    /// let system = DoublePendulumSystem::default();
+    /// let (inner, outer) = system.bob_positions(&DoublePendulumState::new(0.7, -2., 0., 0.));
+    /// let state = DoublePendulumState::from_positions(inner, outer, &system);
+    /// assert!((state.θ1 - 0.7).abs() < 1e-12 && (state.θ2 + 2.).abs() < 1e-12);
+    ///
+    /// // Too far out for unit arms, so both point straight at it
+    /// let state = DoublePendulumState::from_positions(vector![3., 0.], vector![5., 0.], &system);
+    /// let (_, outer) = system.bob_positions(&state);
+    /// assert!((outer - vector![2., 0.]).norm() < 1e-12);
+    /// ```
+    pub fn from_positions(
+        inner: Vector2<f64>,
+        outer: Vector2<f64>,
+        system: &DoublePendulumSystem,
+    ) -> Self {
+        let θ1 = angle_of(inner);
+        let (s1, c1) = θ1.sin_cos();
+        let inner = vector![system.l1 * s1, -system.l1 * c1];
+        let θ2 = angle_of(outer - inner);
+        Self::new(θ1, θ2, 0., 0.)
+    }
+
+    /// Copy of the state with both angles mapped into (-π, π]. The dynamics don't change under
+    /// whole turns, so this only affects how the state is displayed or stored.
+    pub fn wrapped(&self) -> DoublePendulumState {
+        DoublePendulumState {
+            θ1: wrap_angle(self.θ1),
+            θ2: wrap_angle(self.θ2),
+            ..*self
+        }
+    }
+
+    /// Map both angles into (-π, π] in place, see `wrapped`
+    pub fn normalize_in_place(&mut self) {
+        *self = self.wrapped();
+    }
+
+    /// Copy of the state with each angle and angular velocity nudged by up to `magnitude`,
+    /// drawn from an RNG seeded with `seed` so the same arguments always give the same result
+    pub fn perturbed(&self, seed: u64, magnitude: f64) -> DoublePendulumState {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut nudge = |x: f64| x + magnitude * rng.gen_range(-1.0..=1.0);
+        DoublePendulumState {
+            θ1: nudge(self.θ1),
+            θ2: nudge(self.θ2),
+            ω1: nudge(self.ω1),
+            ω2: nudge(self.ω2),
+            t: self.t,
+        }
+    }
+
+    /// How far the joint between the arms is bent, θ1 - θ2 wrapped into (-π, π]
+    pub fn relative_angle(&self) -> f64 {
+        wrap_angle(self.θ1 - self.θ2)
+    }
+
+    /// Whether every angle and angular velocity is a finite number
+    pub fn is_finite(&self) -> bool {
+        self.as_mat().iter().all(|x| x.is_finite())
     }
 
     fn as_mat(self) -> OVector<f64, Const<4>> {
         vector![self.θ1, self.θ2, self.ω1, self.ω2]
     }
+
+    fn from_mat(mat: &OVector<f64, Const<4>>, t: f64) -> Self {
+        Self {
+            t,
+            ..Self::new(mat.x, mat.y, mat.z, mat.w)
+        }
+    }
+
+    // Stand-in for the result of an integrator that gave up, all NaN so `checked_step` treats it
+    // like any other divergence
+    fn failed(t: f64) -> Self {
+        Self {
+            t,
+            ..Self::new(f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+        }
+    }
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct DoublePendulumSystem {
-    // Gravity
+    // Gravity, a negative value pulls upwards so the pendulum rests pointing straight up
     pub g: f64,
     // Weight of inner pendulum
     pub m1: f64,
@@ -34,24 +154,612 @@ pub struct DoublePendulumSystem {
     pub l1: f64,
     /// Length of outer pendulum
     pub l2: f64,
+    /// Viscous damping coefficient applied to both angular velocities
+    pub b: f64,
+    /// Quadratic (air) drag coefficient, slowing each arm by `c_drag * ω * |ω|`
+    pub c_drag: f64,
+    /// Amplitude of the sinusoidal driving torque on the inner pendulum
+    pub drive_amp: f64,
+    /// Angular frequency of the driving torque
+    pub drive_freq: f64,
+    /// Rate the pendulum's plane spins at about the vertical through the pivot, see `deriv`
+    pub omega_frame: f64,
+    /// Whether the inner bob is pinned to a circle of `pin_radius` about the pivot and driven
+    /// around it at `pin_freq` instead of swinging, leaving only the outer arm free. `l1`, `ω1`,
+    /// the drive and the spinning frame are then ignored, see `pinned_inner`.
+    pub pinned: bool,
+    /// Radius of the circle the pinned inner bob is driven around
+    pub pin_radius: f64,
+    /// Angular frequency the pinned inner bob goes around at, counter-clockwise positive
+    pub pin_freq: f64,
+    /// Whether the outer pendulum exists. When disabled, m2, l2, θ2 and ω2 are ignored and the
+    /// system is a simple pendulum (the symplectic solver always assumes both arms).
+    pub enabled2: bool,
+    /// Integrator used by `advance`, and so by `checked_step`, `step_all` and `iter_states`
+    pub solver: Solver,
+    /// Relative and absolute tolerance of the adaptive solver
+    pub tol: f64,
+    /// Custom forcing added to the equations of motion, see `ExternalTorque`. Not saved with
+    /// the rest of the parameters.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub torque: Option<Arc<dyn ExternalTorque>>,
+    /// After every RK4 step, rescale the angular velocities so the total energy is back where
+    /// it was before the step, see `restore_energy`. Only applies to conservative systems.
+    pub energy_correct: bool,
+}
+
+/// User defined forcing on the arms, for magnets, springs or anything else `deriv` doesn't
+/// model. Set one as `DoublePendulumSystem::torque`.
+pub trait ExternalTorque: fmt::Debug + Send + Sync {
+    /// Extra angular accelerations of the inner and outer arm at time `t` in `state`, added
+    /// straight to the ones `deriv` works out like the driving torque is. The outer one is
+    /// ignored when the second arm is disabled.
+    fn torque(&self, t: f64, state: &DoublePendulumState) -> (f64, f64);
+}
+
+/// The same angular accelerations at all times, e.g. a motor holding a steady torque
+///
+/// ```
+/// use nannou_test::dbl_pendulum::{
+///     double_pendulum_deriv, ConstantTorque, DoublePendulumState, DoublePendulumSystem,
+/// };
+/// use std::sync::Arc;
+///
+/// let system = DoublePendulumSystem {
+///     torque: Some(Arc::new(ConstantTorque(1.5, -0.5))),
+///     ..Default::default()
+/// };
+/// // Hanging at rest, the torque is all that's left
+/// let hanging = DoublePendulumState::new(0., 0., 0., 0.);
+/// let (_, _, ωp1, ωp2) = double_pendulum_deriv(&hanging, &system);
+/// assert_eq!((ωp1, ωp2), (1.5, -0.5));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ConstantTorque(pub f64, pub f64);
+
+impl ExternalTorque for ConstantTorque {
+    fn torque(&self, _t: f64, _state: &DoublePendulumState) -> (f64, f64) {
+        (self.0, self.1)
+    }
+}
+
+/// The integrators `DoublePendulumSystem::advance` can use
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Solver {
+    /// Fixed-step RK4, see `DoublePendulumSystem::step`
+    #[default]
+    Rk4,
+    /// Adaptive Dormand-Prince, see `DoublePendulumSystem::step_adaptive`
+    Dopri5,
+    /// Energy conserving leapfrog, see `DoublePendulumSystem::step_symplectic`
+    Symplectic,
+    /// Small-angle approximation, see `DoublePendulumSystem::step_linearized`
+    Linearized,
+}
+
+impl Solver {
+    pub const ALL: [Solver; 4] = [
+        Solver::Rk4,
+        Solver::Dopri5,
+        Solver::Symplectic,
+        Solver::Linearized,
+    ];
+
+    /// Name to show in the UI
+    pub fn name(self) -> &'static str {
+        match self {
+            Solver::Rk4 => "RK4",
+            Solver::Dopri5 => "RK45 (adaptive)",
+            Solver::Symplectic => "Symplectic",
+            Solver::Linearized => "Linearized",
+        }
+    }
 }
 
 impl DoublePendulumSystem {
+    /// Step with the fixed-step RK4 solver, splitting `delta` into equal substeps of at most
+    /// `MAX_RK4_STEP` so a long frame doesn't turn into one huge, unstable step.
     pub fn step(&self, state: DoublePendulumState, delta: f64) -> DoublePendulumState {
-        let mut solver = Rk4::new(self, 0., state.as_mat(), delta, delta);
-        solver.integrate().unwrap();
+        let substeps = ((delta.abs() / MAX_RK4_STEP).ceil() as usize).max(1);
+        let h = delta / substeps as f64;
+        let target =
+            (self.energy_correct && self.is_conservative()).then(|| self.total_energy(&state));
+        let mut state = state;
+        for _ in 0..substeps {
+            state = self.rk4_step(state, h);
+            if let Some(energy) = target {
+                state = self.restore_energy(state, energy);
+            }
+        }
+        state
+    }
+
+    /// Scale both angular velocities of `state` by the same factor so its total energy becomes
+    /// `energy`, keeping the angles. This isn't physics, just a crude way of stopping RK4's
+    /// energy drift on long undamped runs. When the kinetic energy would have to be negative, or
+    /// there's no motion to scale, the state is returned as is.
+    pub fn restore_energy(&self, state: DoublePendulumState, energy: f64) -> DoublePendulumState {
+        let kinetic = self.kinetic_energy(&state);
+        let wanted = energy - self.potential_energy(&state);
+        if kinetic <= 0. || wanted < 0. {
+            return state;
+        }
+        let k = (wanted / kinetic).sqrt();
+        DoublePendulumState {
+            ω1: state.ω1 * k,
+            ω2: state.ω2 * k,
+            ..state
+        }
+    }
+
+    /// Whether the total energy is conserved, so no damping, drag, driving, spinning frame,
+    /// external torque or pinned inner bob
+    pub fn is_conservative(&self) -> bool {
+        self.b == 0.
+            && self.c_drag == 0.
+            && self.drive_amp == 0.
+            && self.omega_frame == 0.
+            && self.torque.is_none()
+            && !self.pinned
+    }
+
+    /// Position relative to the pivot and acceleration of the pinned inner bob at angle `θ1`,
+    /// going uniformly around its circle. The acceleration is the centripetal
+    /// `-pin_freq² * position`, which the outer arm feels as a pseudo-force.
+    ///
+    /// ```
+    /// use nannou_test::dbl_pendulum::DoublePendulumSystem;
+    ///
+    /// let system = DoublePendulumSystem {
+    ///     pinned: true,
+    ///     pin_radius: 0.5,
+    ///     pin_freq: 2.,
+    ///     ..Default::default()
+    /// };
+    /// // Straight down, pulled straight back up towards the pivot
+    /// let (position, acceleration) = system.pinned_inner(0.);
+    /// assert_eq!((position.x, position.y), (0., -0.5));
+    /// assert_eq!((acceleration.x, acceleration.y), (0., 2.));
+    /// ```
+    pub fn pinned_inner(&self, θ1: f64) -> (Vector2<f64>, Vector2<f64>) {
+        let (s1, c1) = θ1.sin_cos();
+        let position = vector![self.pin_radius * s1, -self.pin_radius * c1];
+        (position, -position * self.pin_freq * self.pin_freq)
+    }
+
+    /// Length and angular velocity of the inner arm, the circle the bob is driven around when
+    /// it's pinned
+    fn inner_arm(&self, state: &DoublePendulumState) -> (f64, f64) {
+        if self.pinned {
+            (self.pin_radius, self.pin_freq)
+        } else {
+            (self.l1, state.ω1)
+        }
+    }
+
+    /// Step with whichever integrator `solver` picks
+    pub fn advance(&self, state: DoublePendulumState, delta: f64) -> DoublePendulumState {
+        match self.solver {
+            Solver::Rk4 => self.step(state, delta),
+            Solver::Dopri5 => self.step_adaptive(state, delta, self.tol),
+            Solver::Symplectic => {
+                let substeps = (delta.abs() / MAX_RK4_STEP).ceil() as usize;
+                self.step_symplectic(state, delta, substeps)
+            }
+            Solver::Linearized => self.step_linearized(state, delta),
+        }
+    }
+
+    /// Like `advance`, but returns `None` instead of a state with NaN or infinite components,
+    /// including when the integrator gives up
+    pub fn checked_step(
+        &self,
+        state: DoublePendulumState,
+        delta: f64,
+    ) -> Option<DoublePendulumState> {
+        Some(self.advance(state, delta)).filter(DoublePendulumState::is_finite)
+    }
+
+    /// Run `steps` steps of `dt` from `initial` without any rendering, returning the whole
+    /// trajectory including `initial` (so `steps + 1` states)
+    pub fn simulate(
+        &self,
+        initial: DoublePendulumState,
+        dt: f64,
+        steps: usize,
+    ) -> Vec<DoublePendulumState> {
+        self.iter_states(initial, dt).take(steps + 1).collect()
+    }
+
+    /// Like `simulate`, but advancing `substeps` equal steps of `dt / substeps` between samples,
+    /// for more accurate reference trajectories at the same spacing. Zero substeps are taken as
+    /// one.
+    pub fn simulate_substepped(
+        &self,
+        initial: DoublePendulumState,
+        dt: f64,
+        steps: usize,
+        substeps: usize,
+    ) -> Vec<DoublePendulumState> {
+        let substeps = substeps.max(1);
+        let h = dt / substeps as f64;
+        iter::successors(Some(initial), |state| {
+            Some((0..substeps).fold(*state, |state, _| self.advance(state, h)))
+        })
+        .take(steps + 1)
+        .collect()
+    }
+
+    /// Lazily yield `initial` and every state after it, `dt` apart. The iterator never ends and
+    /// can be cloned to branch off a copy of the run.
+    pub fn iter_states(
+        &self,
+        initial: DoublePendulumState,
+        dt: f64,
+    ) -> impl Iterator<Item = DoublePendulumState> + Clone + '_ {
+        iter::successors(Some(initial), move |state| Some(self.advance(*state, dt)))
+    }
+
+    /// Step every state in `states` by `delta` with `checked_step`, leaving any that would
+    /// diverge where they are. Runs in parallel with the `parallel` feature.
+    pub fn step_all(&self, states: &mut [DoublePendulumState], delta: f64) {
+        let step = |state: &mut DoublePendulumState| {
+            *state = self.checked_step(*state, delta).unwrap_or(*state);
+        };
+        #[cfg(feature = "parallel")]
+        states.par_iter_mut().for_each(step);
+        #[cfg(not(feature = "parallel"))]
+        states.iter_mut().for_each(step);
+    }
+
+    // A single RK4 step of size `h`
+    fn rk4_step(&self, state: DoublePendulumState, h: f64) -> DoublePendulumState {
+        let system = TimeShifted::new(self, state.t);
+        let mut solver = Rk4::new(system, 0., state.as_mat(), h, h);
+        if solver.integrate().is_err() {
+            return DoublePendulumState::failed(state.t + h);
+        }
+        let out = solver.y_out();
+        let out = &out[out.len() - 1];
+        DoublePendulumState::from_mat(out, state.t + h)
+    }
+
+    /// Step using the adaptive Dormand-Prince (RK45) solver, taking as many internal steps as
+    /// needed to keep the local error under `tol` (used as both the relative and absolute
+    /// tolerance). If the solver gives up, say by running out of steps, the state comes back as
+    /// NaN.
+    pub fn step_adaptive(
+        &self,
+        state: DoublePendulumState,
+        delta: f64,
+        tol: f64,
+    ) -> DoublePendulumState {
+        let system = TimeShifted::new(self, state.t);
+        let mut solver = Dopri5::new(system, 0., delta, delta, state.as_mat(), tol, tol);
+        if solver.integrate().is_err() {
+            return DoublePendulumState::failed(state.t + delta);
+        }
+        // Dense output also holds the rows at t = 0 and any intermediate points, the last one is
+        // always the state at t = delta
         let out = solver.y_out();
         let out = &out[out.len() - 1];
+        DoublePendulumState::from_mat(out, state.t + delta)
+    }
+
+    /// Step using the generalized (implicit) leapfrog / Störmer-Verlet scheme on the Hamiltonian
+    /// form of the system, splitting `delta` into `substeps` steps.
+    ///
+    /// Being symplectic, this keeps the energy error bounded over arbitrarily long runs instead
+    /// of letting it drift like RK4 does. Only the conservative dynamics are integrated, damping
+    /// and driving are ignored, and so are a spinning frame, any external torque and pinning.
+    pub fn step_symplectic(
+        &self,
+        state: DoublePendulumState,
+        delta: f64,
+        substeps: usize,
+    ) -> DoublePendulumState {
+        let substeps = substeps.max(1);
+        let h = delta / substeps as f64;
+        let (mut θ1, mut θ2) = (state.θ1, state.θ2);
+        let (mut p1, mut p2) = self.momenta(&state);
+        for _ in 0..substeps {
+            // Half step in momentum, implicit in the new momentum
+            let (mut ph1, mut ph2) = (p1, p2);
+            for _ in 0..SYMPLECTIC_ITERATIONS {
+                let (dθ1, dθ2) = self.dh_dθ(θ1, θ2, ph1, ph2);
+                ph1 = p1 - 0.5 * h * dθ1;
+                ph2 = p2 - 0.5 * h * dθ2;
+            }
+
+            // Full step in position, implicit in the new position
+            let (ω1, ω2) = self.velocities(θ1, θ2, ph1, ph2);
+            let (mut qn1, mut qn2) = (θ1 + h * ω1, θ2 + h * ω2);
+            for _ in 0..SYMPLECTIC_ITERATIONS {
+                let (ωn1, ωn2) = self.velocities(qn1, qn2, ph1, ph2);
+                qn1 = θ1 + 0.5 * h * (ω1 + ωn1);
+                qn2 = θ2 + 0.5 * h * (ω2 + ωn2);
+            }
+            θ1 = qn1;
+            θ2 = qn2;
+
+            // Second half step in momentum, explicit
+            let (dθ1, dθ2) = self.dh_dθ(θ1, θ2, ph1, ph2);
+            p1 = ph1 - 0.5 * h * dθ1;
+            p2 = ph2 - 0.5 * h * dθ2;
+        }
+        let (ω1, ω2) = self.velocities(θ1, θ2, p1, p2);
         DoublePendulumState {
-            θ1: out.x,
-            θ2: out.y,
-            ω1: out.z,
-            ω2: out.w,
+            t: state.t + delta,
+            ..DoublePendulumState::new(θ1, θ2, ω1, ω2)
+        }
+    }
+
+    /// Advance `state` by `delta` using the exact solution of the equations of motion linearized
+    /// about hanging straight down, a superposition of the two normal modes.
+    ///
+    /// This is only a good approximation for small swings. The frequency error grows with the
+    /// square of the amplitude: a few degrees track the real motion for a long time, past 20°
+    /// the two visibly drift out of phase within a few periods, and past 45° or so the linear
+    /// motion is meaningless. Damping, driving, `omega_frame`, `torque` and `pinned` are ignored.
+    pub fn step_linearized(&self, state: DoublePendulumState, delta: f64) -> DoublePendulumState {
+        let t = state.t + delta;
+        if !self.enabled2 {
+            let λ = self.g / self.l1;
+            let (c, s) = (mode_cos(λ, delta), mode_sin(λ, delta));
+            return DoublePendulumState {
+                θ1: c * state.θ1 + s * state.ω1,
+                ω1: -λ * s * state.θ1 + c * state.ω1,
+                t,
+                ..state
+            };
+        }
+        // M θ'' + K θ = 0, so θ'' = -A θ with A = M⁻¹ K
+        let a = self.linearized_matrix(0., 0.);
+        // θ(t) = cos(√A t) θ0 + sin(√A t) / √A ω0 and its derivative, evaluated on the two
+        // eigenvalues of A (the squared normal mode frequencies)
+        let c = matrix_fn(&a, |λ| mode_cos(λ, delta));
+        let s = matrix_fn(&a, |λ| mode_sin(λ, delta));
+        let θ0 = Vector2::new(state.θ1, state.θ2);
+        let ω0 = Vector2::new(state.ω1, state.ω2);
+        let θ = c * θ0 + s * ω0;
+        let ω = -a * s * θ0 + c * ω0;
+        DoublePendulumState {
+            t,
+            ..DoublePendulumState::new(θ.x, θ.y, ω.x, ω.y)
+        }
+    }
+
+    /// The configurations the pendulum can rest in, at zero velocity: down-down, down-up,
+    /// up-down and up-up, with "up" meaning an angle of π. Only down and up when the second arm
+    /// is disabled.
+    pub fn equilibria(&self) -> Vec<DoublePendulumState> {
+        let arm2 = if self.enabled2 {
+            vec![0., PI]
+        } else {
+            vec![0.]
+        };
+        [0., PI]
+            .into_iter()
+            .flat_map(|θ1| {
+                arm2.iter()
+                    .map(move |&θ2| DoublePendulumState::new(θ1, θ2, 0., 0.))
+            })
+            .collect()
+    }
+
+    /// Eigenvalues of `A` in the equations of motion linearized about the equilibrium
+    /// `state`, `δθ'' = -A δθ`, smallest first. Positive ones are the squared frequencies of
+    /// small oscillations, negative ones rates at which small offsets grow exponentially.
+    ///
+    /// ```
+    /// use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+    ///
+    /// // A lone arm hanging down swings at √(g / l)
+    /// let system = DoublePendulumSystem {
+    ///     enabled2: false,
+    ///     ..Default::default()
+    /// };
+    /// let (_, λ) = system.linearized_eigenvalues(&DoublePendulumState::new(0., 0., 0., 0.));
+    /// assert!((λ - system.g / system.l1).abs() < 1e-12);
+    /// ```
+    pub fn linearized_eigenvalues(&self, state: &DoublePendulumState) -> (f64, f64) {
+        if !self.enabled2 {
+            let λ = self.g / self.l1 * state.θ1.cos();
+            return (λ, λ);
+        }
+        let a = self.linearized_matrix(state.θ1, state.θ2);
+        // M⁻¹ K with M positive definite and K symmetric, so the eigenvalues are real
+        let half_trace = a.trace() / 2.;
+        let disc = (half_trace * half_trace - a.determinant()).max(0.).sqrt();
+        (half_trace - disc, half_trace + disc)
+    }
+
+    /// `A = M⁻¹ K` of the equations of motion `M δθ'' + K δθ = 0` linearized about the
+    /// equilibrium at `θ1`, `θ2`
+    fn linearized_matrix(&self, θ1: f64, θ2: f64) -> Matrix2<f64> {
+        let (g, m1, m2, l1, l2) = (self.g, self.m1, self.m2, self.l1, self.l2);
+        let coupling = m2 * l1 * l2 * (θ1 - θ2).cos();
+        let mass = Matrix2::new((m1 + m2) * l1 * l1, coupling, coupling, m2 * l2 * l2);
+        let stiffness = Matrix2::new(
+            (m1 + m2) * g * l1 * θ1.cos(),
+            0.,
+            0.,
+            m2 * g * l2 * θ2.cos(),
+        );
+        mass.try_inverse().unwrap_or_else(Matrix2::zeros) * stiffness
+    }
+
+    /// Whether small nudges away from the equilibrium `state` stay small, so every mode of the
+    /// linearized motion oscillates rather than growing. Damping only makes an equilibrium more
+    /// stable and is ignored, and so is anything else `step_linearized` ignores.
+    pub fn is_stable(&self, state: &DoublePendulumState) -> bool {
+        let (smallest, _) = self.linearized_eigenvalues(state);
+        smallest > 0.
+    }
+
+    /// Total mechanical energy of the system in the given state, the sum of `kinetic_energy` and
+    /// `potential_energy`
+    pub fn total_energy(&self, state: &DoublePendulumState) -> f64 {
+        self.kinetic_energy(state) + self.potential_energy(state)
+    }
+
+    /// Kinetic energy of both pendulums
+    pub fn kinetic_energy(&self, state: &DoublePendulumState) -> f64 {
+        let (m1, m2, l2) = (self.m1, self.m2, self.l2);
+        let (l1, ω1) = self.inner_arm(state);
+        let ω2 = state.ω2;
+        if !self.enabled2 {
+            return 0.5 * m1 * l1 * l1 * ω1 * ω1;
+        }
+        let dc = (state.θ1 - state.θ2).cos();
+        0.5 * m1 * l1 * l1 * ω1 * ω1
+            + 0.5 * m2 * (l1 * l1 * ω1 * ω1 + l2 * l2 * ω2 * ω2 + 2. * l1 * l2 * ω1 * ω2 * dc)
+    }
+
+    /// Gravitational potential energy of both pendulums.
+    ///
+    /// The zero is at the height of the pivot, with depth measured downward positive, so a
+    /// pendulum hanging straight down has `-m * g * l` and one pointing straight up `m * g * l`.
+    pub fn potential_energy(&self, state: &DoublePendulumState) -> f64 {
+        let (g, m1, m2, l2) = (self.g, self.m1, self.m2, self.l2);
+        let (l1, _) = self.inner_arm(state);
+        let depth1 = l1 * state.θ1.cos();
+        if !self.enabled2 {
+            return -g * m1 * depth1;
+        }
+        let depth2 = depth1 + l2 * state.θ2.cos();
+        -g * (m1 * depth1 + m2 * depth2)
+    }
+
+    /// Positions of the top and bottom pendulums relative to the pivot, with x pointing right and
+    /// y pointing up
+    pub fn bob_positions(&self, state: &DoublePendulumState) -> (Vector2<f64>, Vector2<f64>) {
+        let (s1, c1) = state.θ1.sin_cos();
+        let (s2, c2) = state.θ2.sin_cos();
+        let (l1, _) = self.inner_arm(state);
+        let top = vector![l1 * s1, -l1 * c1];
+        let bottom = top + vector![self.l2 * s2, -self.l2 * c2];
+        (top, bottom)
+    }
+
+    /// Center of mass of the bobs relative to the pivot, in the same axes as `bob_positions`
+    ///
+    /// ```
+    /// use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+    ///
+    /// let system = DoublePendulumSystem::default();
+    /// let state = DoublePendulumState::new(0.4, -1.1, 0., 0.);
+    /// let (top, bottom) = system.bob_positions(&state);
+    /// // Equal masses balance halfway between the bobs
+    /// assert!((system.center_of_mass(&state) - (top + bottom) / 2.).norm() < 1e-12);
+    /// ```
+    pub fn center_of_mass(&self, state: &DoublePendulumState) -> Vector2<f64> {
+        let (top, bottom) = self.bob_positions(state);
+        if !self.enabled2 {
+            return top;
+        }
+        (top * self.m1 + bottom * self.m2) / (self.m1 + self.m2)
+    }
+
+    /// How close the outer bob comes to the inner arm, from the pivot to the inner bob. The
+    /// ideal pendulum lets the arms pass through each other, so this is only for show.
+    pub fn arm_clearance(&self, state: &DoublePendulumState) -> f64 {
+        let (top, bottom) = self.bob_positions(state);
+        point_segment_distance(bottom, Vector2::zeros(), top)
+    }
+
+    /// Linear speed of the bottom pendulum, or the top one when the second arm is disabled
+    pub fn tip_speed(&self, state: &DoublePendulumState) -> f64 {
+        let (l1, ω1) = self.inner_arm(state);
+        let (l2, ω2) = (self.l2, state.ω2);
+        if !self.enabled2 {
+            return (l1 * ω1).abs();
+        }
+        let dc = (state.θ1 - state.θ2).cos();
+        (l1 * l1 * ω1 * ω1 + l2 * l2 * ω2 * ω2 + 2. * l1 * l2 * ω1 * ω2 * dc).sqrt()
+    }
+
+    /// Canonical momenta (p1, p2) conjugate to θ1 and θ2 for the given state
+    pub fn momenta(&self, state: &DoublePendulumState) -> (f64, f64) {
+        let (m1, m2, l1, l2) = (self.m1, self.m2, self.l1, self.l2);
+        let dc = (state.θ1 - state.θ2).cos();
+        let p1 = (m1 + m2) * l1 * l1 * state.ω1 + m2 * l1 * l2 * state.ω2 * dc;
+        let p2 = m2 * l2 * l2 * state.ω2 + m2 * l1 * l2 * state.ω1 * dc;
+        (p1, p2)
+    }
+
+    /// Angular momentum of the bobs about the pivot, counter-clockwise positive
+    ///
+    /// Gravity exerts a torque about the pivot whenever the center of mass is off the
+    /// vertical, so unlike the energy this isn't conserved. It equals `p1 + p2`.
+    ///
+    /// ```
+    /// use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+    ///
+    /// // Both arms straight and spinning together: a rigid rod with m1 at l1 and m2 at l1 + l2
+    /// let system = DoublePendulumSystem::default();
+    /// let state = DoublePendulumState::new(0.3, 0.3, 2., 2.);
+    /// let (m1, m2, l1, l2) = (system.m1, system.m2, system.l1, system.l2);
+    /// let rigid = (m1 * l1 * l1 + m2 * (l1 + l2) * (l1 + l2)) * 2.;
+    /// assert!((system.angular_momentum(&state) - rigid).abs() < 1e-12);
+    /// ```
+    pub fn angular_momentum(&self, state: &DoublePendulumState) -> f64 {
+        let (m1, m2, l2) = (self.m1, self.m2, self.l2);
+        let (l1, ω1) = self.inner_arm(state);
+        let ω2 = state.ω2;
+        if !self.enabled2 {
+            return m1 * l1 * l1 * ω1;
         }
+        let dc = (state.θ1 - state.θ2).cos();
+        (m1 + m2) * l1 * l1 * ω1 + m2 * (l2 * l2 * ω2 + l1 * l2 * (ω1 + ω2) * dc)
+    }
+
+    /// Angular velocities (ω1, ω2) for the canonical momenta p1, p2 at angles θ1, θ2
+    pub fn velocities(&self, θ1: f64, θ2: f64, p1: f64, p2: f64) -> (f64, f64) {
+        let (m1, m2, l1, l2) = (self.m1, self.m2, self.l1, self.l2);
+        let (ds, dc) = (θ1 - θ2).sin_cos();
+        let d = m1 + m2 * ds * ds;
+        let ω1 = (l2 * p1 - l1 * p2 * dc) / (l1 * l1 * l2 * d);
+        let ω2 = ((m1 + m2) * l1 * p2 - m2 * l2 * p1 * dc) / (m2 * l1 * l2 * l2 * d);
+        (ω1, ω2)
+    }
+
+    /// Partial derivatives of the Hamiltonian with respect to θ1 and θ2
+    fn dh_dθ(&self, θ1: f64, θ2: f64, p1: f64, p2: f64) -> (f64, f64) {
+        let (g, m1, m2, l1, l2) = (self.g, self.m1, self.m2, self.l1, self.l2);
+        let (ds, dc) = (θ1 - θ2).sin_cos();
+        let d = m1 + m2 * ds * ds;
+        let n = m2 * l2 * l2 * p1 * p1 + (m1 + m2) * l1 * l1 * p2 * p2
+            - 2. * m2 * l1 * l2 * p1 * p2 * dc;
+        let c1 = p1 * p2 * ds / (l1 * l2 * d);
+        let c2 = n * (2. * (θ1 - θ2)).sin() / (2. * l1 * l1 * l2 * l2 * d * d);
+        // Derivative of the kinetic term with respect to θ1 - θ2
+        let dt = c1 - c2;
+        (
+            (m1 + m2) * g * l1 * θ1.sin() + dt,
+            m2 * g * l2 * θ2.sin() - dt,
+        )
     }
 }
 
-const G_EARTH: f64 = 9.80665;
+/// Fixed-point iterations used to solve the implicit stages of `step_symplectic`
+const SYMPLECTIC_ITERATIONS: usize = 8;
+
+/// Smallest mass or length `DoublePendulumSystem::sanitize` lets through
+pub const MIN_PARAM: f64 = 1e-3;
+
+/// Standard gravity at the Earth's surface, m/s²
+pub const G_EARTH: f64 = 9.80665;
+
+/// Surface gravity of a few places worth swinging a pendulum, m/s²
+pub const GRAVITY_PRESETS: [(&str, f64); 5] = [
+    ("Earth", G_EARTH),
+    ("Moon", 1.62),
+    ("Mars", 3.71),
+    ("Jupiter", 24.79),
+    ("Zero-g", 0.),
+];
 
 impl Default for DoublePendulumSystem {
     fn default() -> Self {
@@ -61,14 +769,297 @@ impl Default for DoublePendulumSystem {
             m2: 1.,
             l1: 1.,
             l2: 1.,
+            b: 0.,
+            c_drag: 0.,
+            drive_amp: 0.,
+            drive_freq: 0.,
+            omega_frame: 0.,
+            pinned: false,
+            pin_radius: 0.5,
+            pin_freq: 3.,
+            enabled2: true,
+            solver: Solver::Rk4,
+            tol: ADAPTIVE_TOLERANCE,
+            torque: None,
+            energy_correct: false,
+        }
+    }
+}
+
+impl DoublePendulumSystem {
+    /// Start building a system from the default parameters
+    pub fn builder() -> DoublePendulumSystemBuilder {
+        DoublePendulumSystemBuilder::default()
+    }
+
+    /// Clamp masses and lengths to at least `MIN_PARAM` so the equations of motion never divide
+    /// by zero. NaNs are replaced by `MIN_PARAM` too.
+    pub fn sanitize(&mut self) {
+        for x in [&mut self.m1, &mut self.m2, &mut self.l1, &mut self.l2] {
+            *x = x.max(MIN_PARAM);
+        }
+    }
+}
+
+/// Chainable constructor for `DoublePendulumSystem`, every parameter not set keeps its
+/// `Default` value
+#[derive(Debug, Clone, Default)]
+pub struct DoublePendulumSystemBuilder {
+    system: DoublePendulumSystem,
+}
+
+impl DoublePendulumSystemBuilder {
+    pub fn gravity(mut self, g: f64) -> Self {
+        self.system.g = g;
+        self
+    }
+
+    pub fn mass1(mut self, m: f64) -> Self {
+        self.system.m1 = m;
+        self
+    }
+
+    pub fn mass2(mut self, m: f64) -> Self {
+        self.system.m2 = m;
+        self
+    }
+
+    pub fn length1(mut self, l: f64) -> Self {
+        self.system.l1 = l;
+        self
+    }
+
+    pub fn length2(mut self, l: f64) -> Self {
+        self.system.l2 = l;
+        self
+    }
+
+    /// Finish the system, failing if a mass or length is negative or NaN
+    pub fn build(self) -> Result<DoublePendulumSystem, InvalidParameter> {
+        let s = &self.system;
+        for (name, value) in [("m1", s.m1), ("m2", s.m2), ("l1", s.l1), ("l2", s.l2)] {
+            if value.is_nan() || value < 0. {
+                return Err(InvalidParameter { name, value });
+            }
+        }
+        Ok(self.system)
+    }
+}
+
+/// A parameter passed to `DoublePendulumSystemBuilder` that can't describe a real pendulum
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidParameter {
+    pub name: &'static str,
+    pub value: f64,
+}
+
+impl fmt::Display for InvalidParameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} must be non-negative, got {}", self.name, self.value)
+    }
+}
+
+impl Error for InvalidParameter {}
+
+/// Estimates the largest Lyapunov exponent by following a shadow trajectory that starts `ε`
+/// away in θ2, renormalizing the separation back to `ε` every few steps and averaging the log
+/// of its growth over time
+#[derive(Debug, Clone)]
+pub struct LyapunovEstimator {
+    pub reference: DoublePendulumState,
+    pub perturbed: DoublePendulumState,
+    ε: f64,
+    // Steps between renormalizations
+    renormalize_every: usize,
+    // Steps since the last renormalization
+    steps: usize,
+    // Sum of the log growth of the separation at each renormalization
+    log_sum: f64,
+    // Simulated time since the start
+    elapsed: f64,
+    // Simulated time covered by log_sum
+    measured: f64,
+}
+
+impl LyapunovEstimator {
+    pub fn new(state: DoublePendulumState, ε: f64, renormalize_every: usize) -> Self {
+        let mut perturbed = state;
+        perturbed.θ2 += ε;
+        Self {
+            reference: state,
+            perturbed,
+            ε,
+            renormalize_every: renormalize_every.max(1),
+            steps: 0,
+            log_sum: 0.,
+            elapsed: 0.,
+            measured: 0.,
+        }
+    }
+
+    pub fn step(&mut self, system: &DoublePendulumSystem, dt: f64) {
+        self.reference = system.advance(self.reference, dt);
+        self.perturbed = system.advance(self.perturbed, dt);
+        self.steps += 1;
+        self.elapsed += dt;
+        if self.steps == self.renormalize_every {
+            self.steps = 0;
+            let separation = phase_distance(&self.reference, &self.perturbed);
+            if separation > 0. {
+                self.log_sum += (separation / self.ε).ln();
+                let scale = self.ε / separation;
+                let (r, p) = (&self.reference, &mut self.perturbed);
+                p.θ1 = r.θ1 + (p.θ1 - r.θ1) * scale;
+                p.θ2 = r.θ2 + (p.θ2 - r.θ2) * scale;
+                p.ω1 = r.ω1 + (p.ω1 - r.ω1) * scale;
+                p.ω2 = r.ω2 + (p.ω2 - r.ω2) * scale;
+            }
+            self.measured = self.elapsed;
+        }
+    }
+
+    /// Average exponential growth rate of the separation so far, in 1/s
+    pub fn current_estimate(&self) -> f64 {
+        if self.measured > 0. {
+            self.log_sum / self.measured
+        } else {
+            0.
         }
     }
 }
 
-impl<'a> System<f64, OVector<f64, Const<4>>> for &'a DoublePendulumSystem {
-    fn system(&self, _t: f64, y: &OVector<f64, Const<4>>, dy: &mut OVector<f64, Const<4>>) {
+/// Shortest distance from the point `p` to the line segment from `a` to `b`
+///
+/// ```
+/// use nannou_test::dbl_pendulum::point_segment_distance;
+/// use nalgebra::vector;
+///
+/// let (a, b) = (vector![0., 0.], vector![2., 0.]);
+/// // Beside the segment, past its end and on it
+/// assert_eq!(point_segment_distance(vector![1., 3.], a, b), 3.);
+/// assert_eq!(point_segment_distance(vector![5., 4.], a, b), 5.);
+/// assert_eq!(point_segment_distance(vector![0.5, 0.], a, b), 0.);
+/// ```
+pub fn point_segment_distance(p: Vector2<f64>, a: Vector2<f64>, b: Vector2<f64>) -> f64 {
+    let ab = b - a;
+    let len2 = ab.norm_squared();
+    if len2 == 0. {
+        return (p - a).norm();
+    }
+    let t = ((p - a).dot(&ab) / len2).clamp(0., 1.);
+    (p - (a + ab * t)).norm()
+}
+
+/// Angle from straight down of an arm pointing along `v`, straight down if `v` is zero
+fn angle_of(v: Vector2<f64>) -> f64 {
+    if v == Vector2::zeros() {
+        0.
+    } else {
+        v.x.atan2(-v.y)
+    }
+}
+
+/// Map an angle into (-π, π]
+pub fn wrap_angle(θ: f64) -> f64 {
+    let wrapped = θ.rem_euclid(TAU);
+    if wrapped > PI {
+        wrapped - TAU
+    } else {
+        wrapped
+    }
+}
+
+/// If θ1 swings up through zero between `prev` and `next`, the state at the crossing, linearly
+/// interpolated between the two
+pub fn poincare_crossing(
+    prev: &DoublePendulumState,
+    next: &DoublePendulumState,
+) -> Option<DoublePendulumState> {
+    // Work with sin θ1 so that crossings at any multiple of 2π count, while the cos θ1 check
+    // rules out the top of the swing
+    let (s0, s1) = (prev.θ1.sin(), next.θ1.sin());
+    if !(s0 < 0. && s1 >= 0. && next.θ1.cos() > 0. && next.ω1 > 0.) {
+        return None;
+    }
+    let f = s0 / (s0 - s1);
+    let lerp = |a: f64, b: f64| a + (b - a) * f;
+    Some(DoublePendulumState {
+        θ1: lerp(prev.θ1, next.θ1),
+        θ2: lerp(prev.θ2, next.θ2),
+        ω1: lerp(prev.ω1, next.ω1),
+        ω2: lerp(prev.ω2, next.ω2),
+        t: lerp(prev.t, next.t),
+    })
+}
+
+/// `cos(√λ t)`, continued to `cosh` for negative `λ` (gravity pointing up)
+fn mode_cos(λ: f64, t: f64) -> f64 {
+    if λ > 0. {
+        (λ.sqrt() * t).cos()
+    } else {
+        ((-λ).sqrt() * t).cosh()
+    }
+}
+
+/// `sin(√λ t) / √λ`, continued to `sinh` for negative `λ` and to `t` for `λ = 0`
+fn mode_sin(λ: f64, t: f64) -> f64 {
+    if λ > 0. {
+        let w = λ.sqrt();
+        (w * t).sin() / w
+    } else if λ < 0. {
+        let w = (-λ).sqrt();
+        (w * t).sinh() / w
+    } else {
+        t
+    }
+}
+
+/// Apply `f` to a 2x2 matrix with real eigenvalues using Sylvester's formula. A matrix with a
+/// repeated eigenvalue is treated as a multiple of the identity, which is all a physical
+/// double pendulum can produce.
+fn matrix_fn(a: &Matrix2<f64>, f: impl Fn(f64) -> f64) -> Matrix2<f64> {
+    let half_trace = a.trace() / 2.;
+    let disc = (half_trace * half_trace - a.determinant()).max(0.).sqrt();
+    let (λ1, λ2) = (half_trace + disc, half_trace - disc);
+    if disc <= 1e-12 * half_trace.abs().max(1.) {
+        return Matrix2::identity() * f(half_trace);
+    }
+    let i = Matrix2::identity();
+    (a - i * λ2) * (f(λ1) / (λ1 - λ2)) + (a - i * λ1) * (f(λ2) / (λ2 - λ1))
+}
+
+/// Euclidean distance between two states in (θ1, θ2, ω1, ω2) phase space
+///
+/// ```
+/// use nannou_test::dbl_pendulum::{phase_distance, DoublePendulumState};
+///
+/// let a = DoublePendulumState::new(1., 2., 0., 0.);
+/// let b = DoublePendulumState::new(1., 2., 3., -4.);
+/// assert_eq!(phase_distance(&a, &b), 5.);
+/// assert_eq!(phase_distance(&b, &a), 5.);
+/// assert_eq!(phase_distance(&a, &a), 0.);
+/// ```
+pub fn phase_distance(a: &DoublePendulumState, b: &DoublePendulumState) -> f64 {
+    (a.as_mat() - b.as_mat()).norm()
+}
+
+/// The system as seen by the solvers, which always integrate from 0 while the drive needs the
+/// absolute simulated time
+struct TimeShifted<'a> {
+    system: &'a DoublePendulumSystem,
+    t0: f64,
+}
+
+impl<'a> TimeShifted<'a> {
+    fn new(system: &'a DoublePendulumSystem, t0: f64) -> Self {
+        Self { system, t0 }
+    }
+}
+
+impl System<f64, OVector<f64, Const<4>>> for TimeShifted<'_> {
+    fn system(&self, t: f64, y: &OVector<f64, Const<4>>, dy: &mut OVector<f64, Const<4>>) {
         let (θ1, θ2, ω1, ω2) = (y.x, y.y, y.z, y.w);
-        let (θ1, θ2, ω1, ω2) = deriv(θ1, θ2, ω1, ω2, self.g, self.m1, self.m2, self.l1, self.l2);
+        let (θ1, θ2, ω1, ω2) = deriv(self.t0 + t, θ1, θ2, ω1, ω2, self.system);
         dy.x = θ1;
         dy.y = θ2;
         dy.z = ω1;
@@ -76,31 +1067,186 @@ impl<'a> System<f64, OVector<f64, Const<4>>> for &'a DoublePendulumSystem {
     }
 }
 
+/// The undamped, undriven equations of motion `deriv` integrates, as LaTeX with the numbers of
+/// `system` plugged into the coefficients
+///
+/// ```
+/// use nannou_test::dbl_pendulum::{equations_latex, DoublePendulumSystem};
+///
+/// let system = DoublePendulumSystem {
+///     g: 10.,
+///     m2: 2.5,
+///     ..Default::default()
+/// };
+/// let latex = equations_latex(&system);
+/// // g (2 m1 + m2) and m2 g
+/// assert!(latex.contains("-45 \\sin\\theta_1 - 25 \\sin(\\theta_1 - 2\\theta_2)"));
+/// // The signs follow gravity when it is turned upside down
+/// let flipped = DoublePendulumSystem { g: -10., ..system };
+/// let latex = equations_latex(&flipped);
+/// assert!(latex.contains("{45 \\sin\\theta_1 + 25 \\sin(\\theta_1 - 2\\theta_2)"));
+/// assert!(latex.contains("- 70 \\cos\\theta_1"));
+/// ```
+pub fn equations_latex(system: &DoublePendulumSystem) -> String {
+    let (g, m1, m2, l1, l2) = (system.g, system.m1, system.m2, system.l1, system.l2);
+    if !system.enabled2 {
+        return format!(r"\ddot\theta_1 = {} \sin\theta_1", latex_number(-g / l1));
+    }
+    let ddot1 = format!(
+        r"\ddot\theta_1 = \frac{{{} \sin\theta_1 {} \sin(\theta_1 - 2\theta_2) - \sin(\theta_1 - \theta_2) \left({} \dot\theta_2^2 + {} \dot\theta_1^2 \cos(\theta_1 - \theta_2)\right)}}{{{} - {} \cos(2(\theta_1 - \theta_2))}}",
+        latex_number(-g * (2. * m1 + m2)),
+        latex_term(-m2 * g),
+        latex_number(2. * m2 * l2),
+        latex_number(2. * m2 * l1),
+        latex_number(l1 * (2. * m1 + m2)),
+        latex_number(l1 * m2),
+    );
+    let ddot2 = format!(
+        r"\ddot\theta_2 = \frac{{\sin(\theta_1 - \theta_2) \left({} \dot\theta_1^2 {} \cos\theta_1 + {} \dot\theta_2^2 \cos(\theta_1 - \theta_2)\right)}}{{{} - {} \cos(2(\theta_1 - \theta_2))}}",
+        latex_number(2. * l1 * (m1 + m2)),
+        latex_term(2. * g * (m1 + m2)),
+        latex_number(2. * l2 * m2),
+        latex_number(l2 * (2. * m1 + m2)),
+        latex_number(l2 * m2),
+    );
+    format!(
+        "\\begin{{aligned}}\n{} \\\\\n{}\n\\end{{aligned}}",
+        ddot1, ddot2
+    )
+}
+
+/// A coefficient for `equations_latex`, to four decimal places without trailing zeros
+fn latex_number(x: f64) -> String {
+    let formatted = format!("{:.4}", x);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    // Tiny negative numbers round to zero but keep their sign
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// `x` as a coefficient added to the term before it in `equations_latex`, "+ x" or "- |x|"
+fn latex_term(x: f64) -> String {
+    let sign = if latex_number(x).starts_with('-') {
+        '-'
+    } else {
+        '+'
+    };
+    format!("{} {}", sign, latex_number(x.abs()))
+}
+
+/// Time derivative of `state` under `system`: θ'1, θ'2, ω'1, ω'2
+///
+/// Useful for plugging the equations of motion into other integrators or plotting the vector
+/// field.
+///
+/// ```
+/// use nannou_test::dbl_pendulum::{
+///     double_pendulum_deriv, DoublePendulumState, DoublePendulumSystem,
+/// };
+///
+/// // Hanging straight down at rest is an equilibrium
+/// let hanging = DoublePendulumState::new(0., 0., 0., 0.);
+/// let d = double_pendulum_deriv(&hanging, &DoublePendulumSystem::default());
+/// assert_eq!(d, (0., 0., 0., 0.));
+/// ```
+pub fn double_pendulum_deriv(
+    state: &DoublePendulumState,
+    system: &DoublePendulumSystem,
+) -> (f64, f64, f64, f64) {
+    deriv(state.t, state.θ1, state.θ2, state.ω1, state.ω2, system)
+}
+
+/// The (θ1, ω1) vector field of the inner arm on its own, sampled at time `t` on a grid of
+/// `cols` angles evenly spread over `θ` by `rows` angular velocities evenly spread over `ω`,
+/// both ends included. Each sample is `[θ1, ω1, θ'1, ω'1]`, row by row from the lowest ω1.
+/// The second arm is treated as disabled whatever `system` says.
+pub fn single_pendulum_field(
+    system: &DoublePendulumSystem,
+    t: f64,
+    θ: RangeInclusive<f64>,
+    ω: RangeInclusive<f64>,
+    cols: usize,
+    rows: usize,
+) -> Vec<[f64; 4]> {
+    let single = DoublePendulumSystem {
+        enabled2: false,
+        ..system.clone()
+    };
+    let spread = |range: &RangeInclusive<f64>, i: usize, n: usize| {
+        let f = if n > 1 {
+            i as f64 / (n - 1) as f64
+        } else {
+            0.5
+        };
+        range.start() + (range.end() - range.start()) * f
+    };
+    (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (col, row)))
+        .map(|(col, row)| {
+            let (θ1, ω1) = (spread(&θ, col, cols), spread(&ω, row, rows));
+            let (θp1, _, ωp1, _) = deriv(t, θ1, 0., ω1, 0., &single);
+            [θ1, ω1, θp1, ωp1]
+        })
+        .collect()
+}
+
 /// Derivative for a pendulum system
 ///
 /// Params:
+///  - t: simulated time,
 ///  - θ1: angle of top pendulum,
 ///  - θ2: angle of bottom pendulum,
 ///  - ω1: velocity of angle of top pendulum,
 ///  - ω2: velocity of angle of bottom pendulum,
-///  - g: strength of gravity,
-///  - m1: mass of top pendulum,
-///  - m2: mass of bottom pendulum,
-///  - l1: length of top pendulum,
-///  - l2: length of bottom pendulum,
+///  - system: gravity, masses, lengths, damping and drive of the pendulums,
+///
+/// With the inner bob pinned, the outer arm swings from a pivot accelerating by `a` as the inner
+/// bob goes around, so it feels the pseudo-force `-m2 a` along with gravity:
+/// `l2 θ''2 = -g sin θ2 - a · (cos θ2, sin θ2)`.
 ///
 /// output: θ'1, θ'2, ω'1, ω'2
 fn deriv(
+    t: f64,
     θ1: f64,
     θ2: f64,
     ω1: f64,
     ω2: f64,
-    g: f64,
-    m1: f64,
-    m2: f64,
-    l1: f64,
-    l2: f64,
+    system: &DoublePendulumSystem,
 ) -> (f64, f64, f64, f64) {
+    let (g, m1, m2, l1, l2, b) = (
+        system.g, system.m1, system.m2, system.l1, system.l2, system.b,
+    );
+    let drive = system.drive_amp * (system.drive_freq * t).sin();
+    let drag = |ω: f64| b * ω + system.c_drag * ω * ω.abs();
+    let spin = system.omega_frame;
+    let (τ1, τ2) = match &system.torque {
+        Some(torque) => torque.torque(
+            t,
+            &DoublePendulumState {
+                θ1, θ2, ω1, ω2, t
+            },
+        ),
+        None => (0., 0.),
+    };
+    if system.pinned {
+        // The inner bob goes around its circle regardless, only the outer arm responds
+        if !system.enabled2 {
+            return (system.pin_freq, 0., 0., 0.);
+        }
+        let (_, inner) = system.pinned_inner(θ1);
+        let (s2, c2) = θ2.sin_cos();
+        let ωp2 = -(g * s2 + inner.x * c2 + inner.y * s2) / l2 - drag(ω2) + τ2;
+        return (system.pin_freq, ω2, 0., ωp2);
+    }
+    if !system.enabled2 {
+        let mut ωp1 = -g / l1 * θ1.sin() - drag(ω1) + drive + τ1;
+        if spin != 0. {
+            ωp1 += spin * spin * θ1.sin() * θ1.cos();
+        }
+        return (ω1, 0., ωp1, 0.);
+    }
     let dc = (θ1 - θ2).cos();
     let ds = (θ1 - θ2).sin();
     let tdc = (2. * (θ1 - θ2)).cos();
@@ -108,10 +1254,37 @@ fn deriv(
         - m2 * g * (θ1 - 2. * θ2).sin()
         - 2. * ds * m2 * (ω2 * ω2 * l2 + ω1 * ω1 * l1 * dc);
     let denom = l1 * (2. * m1 + m2 - m2 * tdc);
-    let ωp1 = num / denom;
+    let ωp1 = num / denom - drag(ω1) + drive + τ1;
     let num =
         2. * ds * (ω1 * ω1 * l1 * (m1 + m2) + g * (m1 + m2) * θ1.cos() + ω2 * ω2 * l2 * m2 * dc);
     let denom = l2 * (2. * m1 + m2 - m2 * tdc);
-    let ωp2 = num / denom;
+    let ωp2 = num / denom - drag(ω2) + τ2;
+    if spin != 0. {
+        let (δ1, δ2) = rotating_frame_accelerations(θ1, θ2, system);
+        return (ω1, ω2, ωp1 + δ1, ωp2 + δ2);
+    }
     (ω1, ω2, ωp1, ωp2)
 }
+
+/// Extra angular accelerations from spinning the pendulum's plane at `omega_frame` about the
+/// vertical through the pivot.
+///
+/// In the rotating frame each bob feels a centrifugal force `m Ω² x` pushing it away from the
+/// axis, where `x` is its horizontal distance from the pivot. That's the potential
+/// `-Ω² (m1 x1² + m2 x2²) / 2`, whose generalized forces are solved against the mass matrix
+/// here. The Coriolis force `2 m Ω × v` is always perpendicular to the plane, so the hinge
+/// takes it up and it doesn't change the motion within the plane.
+fn rotating_frame_accelerations(θ1: f64, θ2: f64, system: &DoublePendulumSystem) -> (f64, f64) {
+    let (m1, m2, l1, l2) = (system.m1, system.m2, system.l1, system.l2);
+    let spin2 = system.omega_frame * system.omega_frame;
+    let (s1, c1) = θ1.sin_cos();
+    let (s2, c2) = θ2.sin_cos();
+    let x2 = l1 * s1 + l2 * s2;
+    let q1 = spin2 * l1 * c1 * (m1 * l1 * s1 + m2 * x2);
+    let q2 = spin2 * m2 * l2 * c2 * x2;
+    let m11 = (m1 + m2) * l1 * l1;
+    let m12 = m2 * l1 * l2 * (θ1 - θ2).cos();
+    let m22 = m2 * l2 * l2;
+    let det = m11 * m22 - m12 * m12;
+    ((m22 * q1 - m12 * q2) / det, (m11 * q2 - m12 * q1) / det)
+}
@@ -0,0 +1,208 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use nalgebra::{vector, Const, OVector, Vector2};
+use ode_solvers::{Rk4, System};
+use std::f64::consts::TAU;
+
+/// Largest internal step `ElasticDoublePendulumSystem::step` takes, like the rigid solver's
+const MAX_STEP: f64 = 1. / 240.;
+/// Fewest RK4 steps per period of the fastest spring oscillation
+const STEPS_PER_SPRING_PERIOD: f64 = 40.;
+
+/// State of a double pendulum whose arms are springs, angles measured from hanging straight
+/// down like `DoublePendulumState`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ElasticDoublePendulumState {
+    // Top arm angle
+    pub θ1: f64,
+    // Bottom arm angle
+    pub θ2: f64,
+    // Stretched length of the top arm
+    pub r1: f64,
+    // Stretched length of the bottom arm
+    pub r2: f64,
+    // Top arm angle change
+    pub ω1: f64,
+    // Bottom arm angle change
+    pub ω2: f64,
+    // Top arm length change
+    pub v1: f64,
+    // Bottom arm length change
+    pub v2: f64,
+    // Simulated time
+    pub t: f64,
+}
+
+impl ElasticDoublePendulumState {
+    /// The rigid `state` with both springs at their rest lengths and not stretching
+    pub fn from_rigid(state: &DoublePendulumState, system: &ElasticDoublePendulumSystem) -> Self {
+        Self {
+            θ1: state.θ1,
+            θ2: state.θ2,
+            r1: system.l1,
+            r2: system.l2,
+            ω1: state.ω1,
+            ω2: state.ω2,
+            v1: 0.,
+            v2: 0.,
+            t: state.t,
+        }
+    }
+
+    /// The angles and angular velocities, dropping the lengths
+    pub fn to_rigid(&self) -> DoublePendulumState {
+        DoublePendulumState {
+            t: self.t,
+            ..DoublePendulumState::new(self.θ1, self.θ2, self.ω1, self.ω2)
+        }
+    }
+
+    /// Whether every component is a finite number
+    pub fn is_finite(&self) -> bool {
+        self.as_mat().iter().all(|x| x.is_finite())
+    }
+
+    fn as_mat(&self) -> OVector<f64, Const<8>> {
+        OVector::<f64, Const<8>>::from_column_slice(&[
+            self.θ1, self.θ2, self.r1, self.r2, self.ω1, self.ω2, self.v1, self.v2,
+        ])
+    }
+
+    fn from_mat(mat: &OVector<f64, Const<8>>, t: f64) -> Self {
+        Self {
+            θ1: mat[0],
+            θ2: mat[1],
+            r1: mat[2],
+            r2: mat[3],
+            ω1: mat[4],
+            ω2: mat[5],
+            v1: mat[6],
+            v2: mat[7],
+            t,
+        }
+    }
+}
+
+/// A double pendulum with massless spring arms, which can stretch as well as swing. The
+/// lengths are the springs' rest lengths. Damping, driving and the other extras of
+/// `DoublePendulumSystem` aren't modelled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElasticDoublePendulumSystem {
+    // Gravity
+    pub g: f64,
+    /// Weight of inner pendulum
+    pub m1: f64,
+    /// Weight of outer pendulum
+    pub m2: f64,
+    /// Rest length of inner spring
+    pub l1: f64,
+    /// Rest length of outer spring
+    pub l2: f64,
+    /// Spring constant of inner spring, in N/m
+    pub k1: f64,
+    /// Spring constant of outer spring, in N/m
+    pub k2: f64,
+}
+
+impl ElasticDoublePendulumSystem {
+    /// Gravity, masses and lengths of `system`, with both arms replaced by springs of constant
+    /// `k`
+    pub fn from_rigid(system: &DoublePendulumSystem, k: f64) -> Self {
+        Self {
+            g: system.g,
+            m1: system.m1,
+            m2: system.m2,
+            l1: system.l1,
+            l2: system.l2,
+            k1: k,
+            k2: k,
+        }
+    }
+
+    /// Step with fixed-step RK4, splitting `delta` into substeps short enough to follow both
+    /// the swing and the much faster bouncing of stiff springs
+    pub fn step(
+        &self,
+        state: ElasticDoublePendulumState,
+        delta: f64,
+    ) -> ElasticDoublePendulumState {
+        // The inner bob is pulled by both springs, so this bounds every radial frequency
+        let fastest = ((self.k1 + 2. * self.k2) / self.m1.min(self.m2)).sqrt();
+        let max_step = MAX_STEP.min(TAU / fastest / STEPS_PER_SPRING_PERIOD);
+        let substeps = ((delta.abs() / max_step).ceil() as usize).max(1);
+        let h = delta / substeps as f64;
+        let mut y = state.as_mat();
+        for _ in 0..substeps {
+            let mut solver = Rk4::new(self, 0., y, h, h);
+            solver.integrate().unwrap();
+            y = solver.y_out()[1];
+        }
+        ElasticDoublePendulumState::from_mat(&y, state.t + delta)
+    }
+
+    /// Time derivative of `state`: θ'1, θ'2, r'1, r'2, ω'1, ω'2, v'1, v'2
+    ///
+    /// Each bob is accelerated by gravity and the tension of the springs pulling on it, worked
+    /// out in Cartesian coordinates. The inner bob's acceleration is then split into radial and
+    /// tangential parts, `r'' - r ω²` and `r ω' + 2 r' ω`, and so is the outer bob's
+    /// acceleration relative to the inner one.
+    pub fn deriv(&self, state: &ElasticDoublePendulumState) -> [f64; 8] {
+        let s = state;
+        let (s1, c1) = s.θ1.sin_cos();
+        let (s2, c2) = s.θ2.sin_cos();
+        // Along each arm, away from its pivot, and along increasing angle
+        let (e1, n1) = (vector![s1, -c1], vector![c1, s1]);
+        let (e2, n2) = (vector![s2, -c2], vector![c2, s2]);
+        let tension1 = self.k1 * (s.r1 - self.l1);
+        let tension2 = self.k2 * (s.r2 - self.l2);
+        let gravity = vector![0., -self.g];
+        let a1: Vector2<f64> = gravity + (e2 * tension2 - e1 * tension1) / self.m1;
+        let a2: Vector2<f64> = gravity - e2 * tension2 / self.m2;
+        let relative = a2 - a1;
+        [
+            s.ω1,
+            s.ω2,
+            s.v1,
+            s.v2,
+            (a1.dot(&n1) - 2. * s.v1 * s.ω1) / s.r1,
+            (relative.dot(&n2) - 2. * s.v2 * s.ω2) / s.r2,
+            a1.dot(&e1) + s.r1 * s.ω1 * s.ω1,
+            relative.dot(&e2) + s.r2 * s.ω2 * s.ω2,
+        ]
+    }
+
+    /// Total mechanical energy, kinetic plus gravitational plus the energy stored in the
+    /// springs. Gravity's zero is at the height of the pivot, like the rigid system's.
+    pub fn total_energy(&self, state: &ElasticDoublePendulumState) -> f64 {
+        let (p1, p2) = self.bob_positions(state);
+        let (s1, c1) = state.θ1.sin_cos();
+        let (s2, c2) = state.θ2.sin_cos();
+        let u1 = vector![s1, -c1] * state.v1 + vector![c1, s1] * state.r1 * state.ω1;
+        let u2 = u1 + vector![s2, -c2] * state.v2 + vector![c2, s2] * state.r2 * state.ω2;
+        let kinetic = 0.5 * (self.m1 * u1.norm_squared() + self.m2 * u2.norm_squared());
+        let gravity = self.g * (self.m1 * p1.y + self.m2 * p2.y);
+        let stretch1 = state.r1 - self.l1;
+        let stretch2 = state.r2 - self.l2;
+        let springs = 0.5 * (self.k1 * stretch1 * stretch1 + self.k2 * stretch2 * stretch2);
+        kinetic + gravity + springs
+    }
+
+    /// Positions of the top and bottom pendulums relative to the pivot, in the same axes as
+    /// `DoublePendulumSystem::bob_positions`
+    pub fn bob_positions(
+        &self,
+        state: &ElasticDoublePendulumState,
+    ) -> (Vector2<f64>, Vector2<f64>) {
+        let (s1, c1) = state.θ1.sin_cos();
+        let (s2, c2) = state.θ2.sin_cos();
+        let top = vector![state.r1 * s1, -state.r1 * c1];
+        let bottom = top + vector![state.r2 * s2, -state.r2 * c2];
+        (top, bottom)
+    }
+}
+
+impl System<f64, OVector<f64, Const<8>>> for &ElasticDoublePendulumSystem {
+    fn system(&self, t: f64, y: &OVector<f64, Const<8>>, dy: &mut OVector<f64, Const<8>>) {
+        let state = ElasticDoublePendulumState::from_mat(y, t);
+        dy.copy_from_slice(&self.deriv(&state));
+    }
+}
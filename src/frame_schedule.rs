@@ -0,0 +1,50 @@
+/// When to capture the frames of a recording `duration` seconds long at `fps` frames per
+/// second. Frames are due at whole multiples of the interval from the start, and a slow frame
+/// that misses some of them only captures one, so the cadence never drifts or bunches up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameSchedule {
+    interval: f64,
+    duration: f64,
+    // Wall seconds since the recording started
+    elapsed: f64,
+    // Index of the next frame due
+    next: u64,
+}
+
+impl FrameSchedule {
+    pub fn new(duration: f64, fps: f64) -> Self {
+        Self {
+            interval: 1. / fps,
+            duration,
+            elapsed: 0.,
+            next: 0,
+        }
+    }
+
+    /// Let `dt` wall seconds pass, returning the time since the start the frame rendered now
+    /// stands for if one is due, `None` otherwise
+    pub fn advance(&mut self, dt: f64) -> Option<f64> {
+        if self.is_finished() {
+            return None;
+        }
+        let due = self.next as f64 * self.interval;
+        let now = self.elapsed;
+        self.elapsed += dt;
+        if now < due {
+            return None;
+        }
+        // Skip past any frames this one was late for
+        self.next = (now / self.interval).floor() as u64 + 1;
+        Some(now)
+    }
+
+    /// Seconds between frames
+    pub fn interval(&self) -> f64 {
+        self.interval
+    }
+
+    /// Whether the whole duration has passed
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
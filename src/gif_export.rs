@@ -0,0 +1,106 @@
+use nannou::image;
+use nannou_test::frame_schedule::FrameSchedule;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Directory the frames of a GIF are captured to before being encoded
+const FRAMES_DIR: &str = "gif_frames";
+/// NeuQuant sampling speed from 1 (best palette) to 30 (fastest), see `gif::Frame`
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Frames of the main window being captured towards an animated GIF
+#[derive(Debug)]
+pub struct GifRecording {
+    pub schedule: FrameSchedule,
+    // Path of each captured frame and the time since the start it was captured at
+    frames: Vec<(PathBuf, f64)>,
+    // Where the frame to capture this update goes, if one is due
+    pending: Option<PathBuf>,
+    // Start of the recording in Unix seconds, keeping its frames apart from earlier ones that
+    // may still be encoding
+    stamp: u64,
+}
+
+impl GifRecording {
+    /// Start recording `duration` seconds at `fps`, creating the directory the frames go to
+    pub fn start(duration: f64, fps: f64) -> io::Result<Self> {
+        fs::create_dir_all(FRAMES_DIR)?;
+        Ok(Self {
+            schedule: FrameSchedule::new(duration, fps),
+            frames: Vec::new(),
+            pending: None,
+            stamp: crate::unix_time(),
+        })
+    }
+
+    /// Let `dt` wall seconds pass, lining up a capture if a frame is due
+    pub fn advance(&mut self, dt: f64) {
+        self.pending = self.schedule.advance(dt).map(|t| {
+            let name = format!("{}_{:06}.png", self.stamp, self.frames.len());
+            let path = Path::new(FRAMES_DIR).join(name);
+            self.frames.push((path.clone(), t));
+            path
+        });
+    }
+
+    /// Where to capture the frame rendered now, if one is due
+    pub fn pending(&self) -> Option<&Path> {
+        self.pending.as_deref()
+    }
+
+    /// Encode the captured frames into a GIF at `path` on a background thread, deleting them
+    /// once done. The frames have to be written out already, see
+    /// `Window::await_capture_frame_jobs`.
+    pub fn finish(self, path: PathBuf) {
+        let interval = self.schedule.interval();
+        thread::spawn(move || {
+            match encode(&self.frames, interval, &path) {
+                Ok(()) => println!("Wrote {} frames to {}", self.frames.len(), path.display()),
+                Err(e) => eprintln!("Failed to write GIF to {}: {}", path.display(), e),
+            }
+            for (frame, _) in &self.frames {
+                let _ = fs::remove_file(frame);
+            }
+        });
+    }
+}
+
+/// Path in the working directory for a GIF recorded now
+pub fn timestamped_path() -> PathBuf {
+    PathBuf::from(format!("recording_{}.gif", crate::unix_time()))
+}
+
+/// Write the PNG `frames`, each shown until the time of the next one (the last for `interval`),
+/// to a looping GIF at `path` with a palette quantized per frame
+fn encode(frames: &[(PathBuf, f64)], interval: f64, path: &Path) -> io::Result<()> {
+    let Some((first, _)) = frames.first() else {
+        return Err(io::Error::other("no frames were captured"));
+    };
+    let (width, height) = image::image_dimensions(first).map_err(io::Error::other)?;
+    let size = |x: u32| u16::try_from(x).map_err(|_| io::Error::other("window too large"));
+    let (width, height) = (size(width)?, size(height)?);
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(writer, width, height, &[]).map_err(io::Error::other)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(io::Error::other)?;
+    for (i, (frame, t)) in frames.iter().enumerate() {
+        let mut pixels = image::open(frame)
+            .map_err(io::Error::other)?
+            .resize_exact(
+                width as u32,
+                height as u32,
+                image::imageops::FilterType::Nearest,
+            )
+            .to_rgba8()
+            .into_raw();
+        let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, QUANTIZE_SPEED);
+        let next = frames.get(i + 1).map_or(t + interval, |(_, next)| *next);
+        // In hundredths of a second
+        gif_frame.delay = ((next - t) * 100.).round().max(1.) as u16;
+        encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
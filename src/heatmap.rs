@@ -0,0 +1,113 @@
+use nannou::image::{Rgba, RgbaImage};
+use nannou::prelude::*;
+
+/// How often the outer tip visited each cell of a grid laid over the main window
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    // Visits per cell, row by row from the top left
+    counts: Vec<u32>,
+    cols: usize,
+    rows: usize,
+    // Side of a cell in pixels
+    cell: f32,
+}
+
+impl Heatmap {
+    /// An empty grid of `cell` pixel cells covering a `size` window centered on the origin
+    pub fn new(size: Vec2, cell: f32) -> Self {
+        let cols = (size.x / cell).ceil().max(1.) as usize;
+        let rows = (size.y / cell).ceil().max(1.) as usize;
+        Self {
+            counts: vec![0; cols * rows],
+            cols,
+            rows,
+            cell,
+        }
+    }
+
+    /// Size of the area covered in pixels
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.cols as f32, self.rows as f32) * self.cell
+    }
+
+    /// Count a visit to the cell under `p`. Points off the grid count towards the nearest cell
+    /// on its edge.
+    pub fn add(&mut self, p: Vec2) {
+        let i = self.index(p);
+        self.counts[i] = self.counts[i].saturating_add(1);
+    }
+
+    /// Index into `counts` of the cell under `p`, clamped to the grid
+    fn index(&self, p: Vec2) -> usize {
+        let corner = p - self.size() / 2. * Vec2::new(-1., 1.);
+        let col = (corner.x / self.cell)
+            .floor()
+            .clamp(0., (self.cols - 1) as f32);
+        let row = (-corner.y / self.cell)
+            .floor()
+            .clamp(0., (self.rows - 1) as f32);
+        row as usize * self.cols + col as usize
+    }
+
+    /// Halve every count, so old visits fade out relative to new ones
+    pub fn decay(&mut self) {
+        for count in &mut self.counts {
+            *count /= 2;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+    }
+
+    /// One pixel per cell, colored from transparent blue for rare visits to opaque red for the
+    /// most visited cell. Counts are scaled logarithmically, so a few hot spots don't wash out
+    /// the rest.
+    pub fn to_image(&self) -> RgbaImage {
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        let scale = ((max as f32) + 1.).ln().max(f32::EPSILON);
+        RgbaImage::from_fn(self.cols as u32, self.rows as u32, |x, y| {
+            let count = self.counts[y as usize * self.cols + x as usize];
+            if count == 0 {
+                return Rgba([0, 0, 0, 0]);
+            }
+            let t = ((count as f32) + 1.).ln() / scale;
+            let c: Srgb = hsv(2. / 3. * (1. - t), 1., 1.).into();
+            let byte = |v: f32| (v.clamp(0., 1.) * 255.) as u8;
+            Rgba([
+                byte(c.red),
+                byte(c.green),
+                byte(c.blue),
+                byte(0.3 + 0.7 * t),
+            ])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_map_to_cells_from_the_top_left() {
+        // Four columns and two rows of 10 pixel cells
+        let heatmap = Heatmap::new(Vec2::new(40., 20.), 10.);
+        assert_eq!(heatmap.size(), Vec2::new(40., 20.));
+        assert_eq!(heatmap.index(Vec2::new(-15., 5.)), 0);
+        assert_eq!(heatmap.index(Vec2::new(15., 5.)), 3);
+        assert_eq!(heatmap.index(Vec2::new(-15., -5.)), 4);
+        assert_eq!(heatmap.index(Vec2::new(1., -1.)), 6);
+        assert_eq!(heatmap.index(Vec2::new(15., -5.)), 7);
+    }
+
+    #[test]
+    fn points_off_the_grid_clamp_to_its_edge() {
+        let mut heatmap = Heatmap::new(Vec2::new(40., 20.), 10.);
+        assert_eq!(heatmap.index(Vec2::new(-100., 100.)), 0);
+        assert_eq!(heatmap.index(Vec2::new(100., 5.)), 3);
+        assert_eq!(heatmap.index(Vec2::new(-5., -100.)), 5);
+        assert_eq!(heatmap.index(Vec2::new(100., -100.)), 7);
+        heatmap.add(Vec2::new(1000., 1000.));
+        assert_eq!(heatmap.counts, [0, 0, 0, 1, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,9 @@
+//! Double (and N-link) pendulum physics, plus the frame timing of recordings, independent of the
+//! nannou front end
+pub mod basin;
+pub mod dbl_pendulum;
+pub mod elastic_pendulum;
+pub mod frame_schedule;
+pub mod n_pendulum;
+pub mod sweep;
+pub mod trajectory;
@@ -1,82 +1,967 @@
 use nannou::prelude::*;
 use nannou_conrod as ui;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use ui::prelude::*;
-mod dbl_pendulum;
+// The physics lives in the library, re-imported here so the app modules can keep using
+// `crate::dbl_pendulum`
+use nannou_test::dbl_pendulum;
+mod cli;
+#[cfg(feature = "serde")]
+mod config;
+mod contact_sheet;
+mod gif_export;
+mod heatmap;
+mod presets;
+mod recording;
+#[cfg(feature = "serde")]
+mod snapshot;
+mod timeline;
+mod watchdog;
 
-use dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use dbl_pendulum::{
+    equations_latex, phase_distance, poincare_crossing, single_pendulum_field, wrap_angle,
+    DoublePendulumState, DoublePendulumSystem, LyapunovEstimator, Solver, GRAVITY_PRESETS,
+};
+use gif_export::GifRecording;
+use heatmap::Heatmap;
+use nannou_test::elastic_pendulum::{ElasticDoublePendulumState, ElasticDoublePendulumSystem};
+use nannou_test::{basin, sweep, trajectory};
+use recording::TrajectoryRow;
+use timeline::{Change, Event, Param, Replay};
+use watchdog::EnergyWatchdog;
 
+/// Default pixels per meter
 const LEN_SCALE: f64 = 100.;
+/// Range of the zoom slider and mouse wheel, in pixels per meter
+const MIN_LEN_SCALE: f64 = 20.;
+const MAX_LEN_SCALE: f64 = 400.;
+/// Range of the gravity slider in m/s²
+const MAX_G: f64 = 25.;
+/// Range of the pinned inner bob's frequency slider, either way, in rad/s
+const MAX_PIN_FREQ: f64 = 20.;
+/// Range of the mass sliders in kg
+const MIN_MASS: f64 = 0.1;
+const MAX_MASS: f64 = 100.;
+/// Range of the length sliders in meters
+const MIN_LENGTH: f64 = 0.5;
+const MAX_LENGTH: f64 = 5.;
+/// Largest initial angle, either way, picked by randomizing
+const MAX_RANDOM_ANGLE: f64 = 1.;
+/// Factor the zoom changes by per line scrolled with the mouse wheel
+const WHEEL_ZOOM_STEP: f64 = 1.1;
+/// Default size of the main window, overridden with --width and --height
 const WIDTH: u32 = 1024;
 const HEIGHT: u32 = 1024;
+/// Default physics timestep, independent of the frame rate
+const FIXED_DT: f64 = 1. / 240.;
+/// Most physics steps taken in a single frame, any time beyond is dropped. Leaves room for the
+/// time scale slider at its maximum on a slow frame
+const MAX_SUBSTEPS: usize = 128;
+/// Most integration substeps each physics step can be split into from the slider
+const MAX_INTEGRATION_SUBSTEPS: usize = 64;
+/// How many times the substeps are multiplied at the very start of a slow start, easing off to
+/// none by its end
+const RAMP_FACTOR: f64 = 8.;
+/// Longest slow start the slider allows, in simulated seconds
+const MAX_RAMP_TIME: f64 = 10.;
+/// Default number of points kept in the trail
+const TRAIL_CAPACITY: usize = 2000;
+/// Range of the adaptive solver's tolerance slider, as powers of ten
+const MIN_TOL_EXPONENT: f64 = -9.;
+const MAX_TOL_EXPONENT: f64 = -3.;
+/// Change in gravity per press of [ or ], in m/s²
+const G_STEP: f64 = 0.5;
+/// Angle the arrow keys turn an arm by while paused, in degrees
+const NUDGE_DEGREES: f64 = 1.;
+/// Side of a heatmap cell in pixels
+const HEATMAP_CELL: f32 = 2.;
+/// Frames between halvings of the heatmap when it decays
+const HEATMAP_DECAY_EVERY: u64 = 60;
+/// Fastest the trail hue can turn from the slider, in degrees per simulated second
+const MAX_HUE_RATE: f64 = 90.;
+/// Thickest arms the slider allows, in pixels
+const MAX_ARM_WEIGHT: f32 = 10.;
+/// Most frames the trail decimation slider can skip between trail points
+const MAX_TRAIL_EVERY: usize = 30;
+/// Number of points kept in each phase portrait
+const PHASE_CAPACITY: usize = 4000;
+/// Width of the control window's widget column, the phase portraits go to the right of it
+const CONTROLS_WIDTH: u32 = 350;
+/// Height of the control window when the screen size is unknown, and the least it shrinks to
+const CONTROLS_FALLBACK_HEIGHT: u32 = 720;
+/// Screen height left for the title bar and any task bar when sizing the control window
+const SCREEN_MARGIN: f64 = 100.;
+/// Side of each phase portrait in the control window
+const PHASE_PLOT_SIZE: f32 = 280.;
+/// Angles and angular velocities the vector field behind the single pendulum's phase portrait
+/// is sampled at. Odd, so the equilibria fall on the grid
+const FIELD_COLS: usize = 21;
+const FIELD_ROWS: usize = 21;
+/// Longest vector field arrow in pixels
+const FIELD_ARROW: f32 = 10.;
+/// Number of samples kept in the separation plot
+const SEPARATION_CAPACITY: usize = 1200;
+/// Height of the separation plot, which is as wide as the phase portraits
+const SEPARATION_PLOT_HEIGHT: f32 = 200.;
+/// Default bottom pendulum speed mapped to the hottest trail color
+const MAX_SPEED_FOR_SCALE: f64 = 10.;
+/// Radius of a unit mass bob in meters, other bobs scale with the cube root of their mass like
+/// spheres of the same density
+const BOB_RADIUS: f64 = 0.1;
+/// Distance in meters between the outer bob and the inner arm that counts as the arms
+/// crossing
+const CROSSING_DISTANCE: f64 = BOB_RADIUS;
+/// Seconds the outer bob flashes for after the arms cross
+const CROSSING_FLASH: f32 = 0.5;
+/// Smallest bob radius in pixels, so light bobs stay visible and easy to grab
+const MIN_BOB_RADIUS: f32 = 3.;
+/// Largest bob radius as a fraction of the smaller side of the main window
+const MAX_BOB_FRACTION: f32 = 0.1;
+/// How close to the pivot a click has to land to grab it
+const PIVOT_GRAB_RADIUS: f32 = 15.;
+/// Initial separation of the shadow trajectory used to estimate the Lyapunov exponent
+const LYAPUNOV_EPSILON: f64 = 1e-8;
+/// Physics steps between renormalizations of the shadow trajectory
+const LYAPUNOV_RENORMALIZE_EVERY: usize = 10;
+/// Ensemble sizes cycled through with E, 0 being off
+const ENSEMBLE_SIZES: [usize; 4] = [0, 10, 50, 200];
+/// Increments in θ2 between ensemble members cycled through with W
+const ENSEMBLE_SPREADS: [f64; 3] = [1e-4, 1e-6, 1e-2];
+/// Spring constant of the arms of the elastic pendulum, in N/m. Soft enough that gravity
+/// visibly stretches unit masses
+const SPRING_STIFFNESS: f64 = 300.;
+/// Number of coils each spring of the elastic pendulum is drawn with
+const SPRING_COILS: usize = 12;
+/// Default energy drift the watchdog warns about
+const ENERGY_DRIFT_THRESHOLD: f64 = 0.01;
+/// Weight of the newest frame in the smoothed frame rate
+const FPS_SMOOTHING: f64 = 0.05;
+/// Directory captured frames are written to
+const FRAMES_DIR: &str = "frames";
+/// Default length in seconds and frame rate of a GIF recording, overridden with --gif-seconds
+/// and --gif-fps
+const GIF_SECONDS: f64 = 5.;
+const GIF_FPS: f64 = 25.;
+/// Default number of runs in a parameter sweep and simulated seconds each runs for, overridden
+/// with --sweep-count and --sweep-seconds
+const SWEEP_COUNT: usize = 16;
+const SWEEP_SECONDS: f64 = 10.;
+/// Viscous damping used for a basin of attraction when the configured system has none, without
+/// which nothing would settle
+const BASIN_DAMPING: f64 = 0.5;
+/// Simulated seconds after which a run in a basin of attraction counts as settled regardless
+const BASIN_MAX_TIME: f64 = 60.;
 
 widget_ids! {
     struct Ids {
+        controls,
+        controls_scrollbar,
         title,
+        preset_label,
+        preset,
+        solver_label,
+        solver,
+        tol_label,
+        tol,
+        compare_label,
+        compare,
         g_label,
         g,
+        gravity_preset_label,
+        gravity_preset,
         m1_label,
         m1,
         m2_label,
         m2,
         l1_label,
         l1,
+        enabled2,
         l2_label,
         l2,
+        b_label,
+        b,
+        c_drag_label,
+        c_drag,
+        drive_amp_label,
+        drive_amp,
+        drive_freq_label,
+        drive_freq,
+        omega_frame_label,
+        omega_frame,
+        pinned,
+        pin_radius,
+        pin_freq_label,
+        pin_freq,
+        time_scale_label,
+        time_scale,
+        substeps_label,
+        substeps,
+        ramp_time_label,
+        ramp_time,
+        energy_correct,
+        len_scale_label,
+        len_scale,
+        persistence_label,
+        persistence,
+        trail_every_label,
+        trail_every,
+        trail_threshold_label,
+        trail_threshold,
+        hue_rate_label,
+        hue_rate,
+        arm_weight_label,
+        arm_weight,
+        use_degrees,
+        theta1_label,
+        theta1,
+        theta2_label,
+        theta2,
+        total_energy,
+        kinetic_energy,
+        potential_energy,
+        angular_momentum,
+        lyapunov,
+        relative_angle,
+        seed_label,
+        seed,
+        arm1_color_label,
+        arm1_r,
+        arm1_g,
+        arm1_b,
+        arm2_color_label,
+        arm2_r,
+        arm2_g,
+        arm2_b,
+        bob1_color_label,
+        bob1_r,
+        bob1_g,
+        bob1_b,
+        bob2_color_label,
+        bob2_r,
+        bob2_g,
+        bob2_b,
+        phase1_plot,
+        phase2_plot,
+        separation_plot,
+    }
+}
+
+/// Parts of the pendulum that can be grabbed with the mouse
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Drag {
+    Pivot,
+    Top,
+    Bottom,
+}
+
+/// Which bobs have their trails drawn
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Trails {
+    Outer,
+    Inner,
+    Both,
+    Off,
+}
+
+impl Trails {
+    /// The mode after this one when cycling with the trail key
+    fn next(self) -> Self {
+        match self {
+            Trails::Outer => Trails::Inner,
+            Trails::Inner => Trails::Both,
+            Trails::Both => Trails::Off,
+            Trails::Off => Trails::Outer,
+        }
+    }
+
+    fn outer(self) -> bool {
+        matches!(self, Trails::Outer | Trails::Both)
+    }
+
+    fn inner(self) -> bool {
+        matches!(self, Trails::Inner | Trails::Both)
     }
 }
 
 struct Model {
     system: DoublePendulumSystem,
     state: DoublePendulumState,
+    // State the simulation was released from
+    initial_state: DoublePendulumState,
     main_window: WindowId,
+    // Area of the main window, kept up to date as it is resized
+    window_rect: geom::Rect,
     ui: Ui,
     ids: Ids,
+    // Physics timestep
+    fixed_dt: f64,
+    // Number of equal integration steps each physics step is split into
+    substeps: usize,
+    // Simulated seconds at the start of a run that get extra substeps, see `ramp_substeps`
+    ramp_time: f64,
+    // Simulated seconds per wall clock second
+    time_scale: f64,
+    // Zoom and camera offset of the main window
+    view: View,
+    // How much of the previous frame shows through, 0 clears every frame and 1 never fades
+    persistence: f32,
+    // Wall time not yet simulated
+    accumulator: f64,
+    // Whether the simulation is stopped
+    paused: bool,
+    // Whether a step produced a non-finite state and was thrown away
+    diverged: bool,
+    // Total simulated time stepped since launch, across resets
+    sim_time: f64,
+    // Smoothed frames per second
+    fps: f64,
+    // Whether to show the frame rate and timing overlay
+    show_hud: bool,
+    // Recent positions, speeds and simulated times of the bottom pendulum, newest at the back
+    trail: VecDeque<(Vec2, f64, f64)>,
+    // Recent positions of the top pendulum, newest at the back
+    inner_trail: VecDeque<Vec2>,
+    // Which of the trails are drawn
+    trails: Trails,
+    // Maximum number of points kept in the trail
+    trail_capacity: usize,
+    // Only every this many frames add a point to the trails, at least 1
+    trail_every: usize,
+    // Frames since a point was last added to the trails
+    trail_frames: usize,
+    // Degrees per simulated second the trail hue turns by, 0 to color the trail by speed
+    hue_rate: f64,
+    // Tip speed in m/s the run has to reach before the trails start, 0 to trace from the start
+    trail_threshold: f64,
+    // Whether this run has reached the trail threshold yet
+    tracing: bool,
+    // Line weight of the arms in pixels, the trails are drawn half as thick
+    arm_weight: f32,
+    // Colors of the links and bobs, the outer link shifts towards red as the joint bends
+    arm1_color: Srgb,
+    arm2_color: Srgb,
+    bob1_color: Srgb,
+    bob2_color: Srgb,
+    // Speed at which the trail color saturates
+    max_speed_for_scale: f64,
+    // Location of the top pendulum's pivot in the main window
+    pivot: Vec2,
+    // What is being dragged with the mouse, if anything
+    dragging: Option<Drag>,
+    // Contents of the initial angle text boxes, in degrees
+    theta1_text: String,
+    theta2_text: String,
+    // Index of the last preset picked from the drop down
+    preset: Option<usize>,
+    // Source of the seeds for randomizing with X
+    rng: StdRng,
+    // Seed of the current random configuration, if any
+    seed: Option<u64>,
+    // Contents of the seed text box
+    seed_text: String,
+    // Trajectory recorded so far, while recording
+    recording: Option<Vec<TrajectoryRow>>,
+    // Running estimate of how chaotic the current run is
+    lyapunov: LyapunovEstimator,
+    // Whether to show the Poincaré section instead of the pendulum
+    show_poincare: bool,
+    // Whether to draw the reach circles and axes around the pivot
+    show_grid: bool,
+    // Whether to mark the center of mass of the bobs
+    show_com: bool,
+    // Whether to print the live angles and angular velocities in the main window
+    show_state: bool,
+    // Whether to draw the configuration the run was released from
+    show_initial: bool,
+    // Visits of the outer tip to each part of the main window, accumulated while shown
+    heatmap: Heatmap,
+    show_heatmap: bool,
+    // Whether old visits fade out of the heatmap
+    heatmap_decay: bool,
+    // Whether the key bindings are listed over the main window
+    show_help: bool,
+    // Whether angles are shown and entered in degrees rather than radians
+    use_degrees: bool,
+    // Whether to watch for the outer bob passing over the inner arm
+    detect_crossings: bool,
+    // Whether the outer bob was over the inner arm last frame
+    crossing: bool,
+    // Seconds left of the flash marking the last crossing
+    crossing_flash: f32,
+    // (θ2, ω2) each time θ1 swung up through zero
+    poincare_points: Vec<Vec2>,
+    // Copy of the run started LYAPUNOV_EPSILON away in θ2, left to drift apart
+    shadow: DoublePendulumState,
+    // Recent (t, log10 of the phase space distance to the shadow), newest at the back
+    separation: VecDeque<(f64, f32)>,
+    // Recent (θ, ω) of each arm with θ wrapped to (-π, π], newest at the back
+    phase1: VecDeque<Vec2>,
+    phase2: VecDeque<Vec2>,
+    // Pendulums started from nearly the same state as the main one, to show divergence
+    ensemble: Vec<DoublePendulumState>,
+    // Indices into ENSEMBLE_SIZES and ENSEMBLE_SPREADS
+    ensemble_size: usize,
+    ensemble_spread: usize,
+    // Same run under the small-angle approximation, when shown for comparison
+    linear: Option<DoublePendulumState>,
+    // Same run with springs for arms, when shown for comparison
+    elastic: Option<ElasticDoublePendulumState>,
+    // Warns when energy drifts in a run that should conserve it
+    watchdog: Option<EnergyWatchdog>,
+    // Whether every rendered frame is saved to FRAMES_DIR
+    capturing: bool,
+    // Number of the frame being captured, counted up each update while capturing
+    frame_number: u64,
+    // Frames captured so far towards a GIF, while recording one
+    gif: Option<GifRecording>,
+    // Length in seconds and frame rate of the next GIF recording
+    gif_seconds: f64,
+    gif_fps: f64,
+    // Second integrator run alongside the main one from the same state, if comparing
+    compare_solver: Option<Solver>,
+    compare_state: DoublePendulumState,
+    // Parameters as of the last reset, and every change made to them or to the pose since
+    log_start: DoublePendulumSystem,
+    change_log: Vec<Event>,
+    // Parameter changes still being played back, while replaying
+    replay: Option<Replay>,
+    // Samples of a trajectory shown in place of the simulation, sorted by time, and the time
+    // along them shown now
+    playback: Option<Vec<(f64, DoublePendulumState)>>,
+    playback_clock: f64,
 }
 
 impl Model {
+    /// Advance everything by `t`, in `substeps` integration steps or more during a slow start
     fn step(&mut self, t: f64) {
-        self.state = self.system.step(self.state, t);
+        self.system.sanitize();
+        let substeps = ramp_substeps(self.substeps, self.state.t, self.ramp_time);
+        let dt = t / substeps as f64;
+        for _ in 0..substeps {
+            if self.diverged {
+                return;
+            }
+            self.substep(dt);
+        }
+    }
+
+    fn substep(&mut self, t: f64) {
+        if let Some(replay) = &mut self.replay {
+            let due = replay.due(self.state.t).to_vec();
+            if replay.is_finished() {
+                self.replay = None;
+            }
+            for event in due {
+                self.apply(event.change);
+                self.change_log.push(event);
+            }
+        }
+        let prev = self.state;
+        let next = self.system.checked_step(self.state, t);
+        if !take_step(&mut self.state, &mut self.sim_time, next, t) {
+            self.diverged = true;
+            return;
+        }
+        self.lyapunov.step(&self.system, t);
+        self.system.step_all(&mut self.ensemble, t);
+        self.shadow = self
+            .system
+            .checked_step(self.shadow, t)
+            .unwrap_or(self.shadow);
+        if let Some(linear) = &mut self.linear {
+            *linear = self.system.step_linearized(*linear, t);
+        }
+        if let Some(elastic) = &mut self.elastic {
+            let system = ElasticDoublePendulumSystem::from_rigid(&self.system, SPRING_STIFFNESS);
+            let next = system.step(*elastic, t);
+            if next.is_finite() {
+                *elastic = next;
+            }
+        }
+        if let Some(solver) = self.compare_solver {
+            let system = DoublePendulumSystem {
+                solver,
+                ..self.system.clone()
+            };
+            self.compare_state = system
+                .checked_step(self.compare_state, t)
+                .unwrap_or(self.compare_state);
+        }
+        if let Some(crossing) = poincare_crossing(&prev, &self.state) {
+            self.poincare_points.push(Vec2::new(
+                wrap_angle(crossing.θ2) as f32,
+                crossing.ω2 as f32,
+            ));
+        }
+    }
+
+    /// Move the playback clock on by `t`, starting over from the first sample after the last,
+    /// and show the trajectory at the new time
+    fn advance_playback(&mut self, t: f64) {
+        let Some(samples) = &self.playback else {
+            return;
+        };
+        let (start, end) = (samples[0].0, samples[samples.len() - 1].0);
+        self.playback_clock += t;
+        if self.playback_clock > end {
+            self.playback_clock = start;
+            self.trail.clear();
+            self.inner_trail.clear();
+        }
+        if let Some(state) = trajectory::interpolate(samples, self.playback_clock) {
+            self.state = state;
+        }
+    }
+
+    /// Change a parameter from outside the control window, logging it for replays like the
+    /// sliders do
+    fn set_param(&mut self, which: Param, value: f64) {
+        self.log(Change::Param(which, value));
+    }
+
+    /// Make `change` and log it for replays
+    fn log(&mut self, change: Change) {
+        self.apply(change);
+        self.change_log.push(Event {
+            t: self.state.t,
+            change,
+        });
+    }
+
+    /// Make `change`, keeping everything that follows the pose in step with a drag
+    fn apply(&mut self, change: Change) {
+        change.apply(&mut self.system, &mut self.state);
+        if let Change::Drag(..) = change {
+            self.hold_still();
+        }
+    }
+
+    /// Rerun from the initial state with the parameters as they were at the last reset, making
+    /// the same changes to them and the same drags at the same simulated times
+    fn start_replay(&mut self) {
+        let events = std::mem::take(&mut self.change_log);
+        println!("Replaying {} changes", events.len());
+        self.system = self.log_start.clone();
+        self.reset();
+        self.replay = Some(Replay::new(events));
+    }
+
+    /// Restart the simulation from the initial state
+    fn reset(&mut self) {
+        self.state = self.initial_state;
+        if let Some(&(t, state)) = self.playback.as_ref().and_then(|samples| samples.first()) {
+            self.playback_clock = t;
+            self.state = state;
+        }
+        self.log_start = self.system.clone();
+        self.change_log.clear();
+        self.replay = None;
+        self.diverged = false;
+        self.accumulator = 0.;
+        self.trail.clear();
+        self.inner_trail.clear();
+        self.heatmap.clear();
+        self.trail_frames = 0;
+        self.tracing = false;
+        self.lyapunov = new_lyapunov(self.state);
+        self.poincare_points.clear();
+        self.phase1.clear();
+        self.phase2.clear();
+        self.shadow = new_shadow(self.state);
+        self.separation.clear();
+        self.compare_state = self.state;
+        self.respawn_ensemble();
+        if self.linear.is_some() {
+            self.linear = Some(self.state);
+        }
+        if self.elastic.is_some() {
+            self.elastic = Some(self.new_elastic());
+        }
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.rebaseline();
+        }
+    }
+
+    /// The current state with the arms as unstretched springs
+    fn new_elastic(&self) -> ElasticDoublePendulumState {
+        let system = ElasticDoublePendulumSystem::from_rigid(&self.system, SPRING_STIFFNESS);
+        ElasticDoublePendulumState::from_rigid(&self.state, &system)
+    }
+
+    /// Stop the pendulum where it was just placed by hand, restarting anything that follows the
+    /// main run from there
+    fn hold_still(&mut self) {
+        self.state.ω1 = 0.;
+        self.state.ω2 = 0.;
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.rebaseline();
+        }
+        if self.linear.is_some() {
+            self.linear = Some(self.state);
+        }
+        if self.elastic.is_some() {
+            self.elastic = Some(self.new_elastic());
+        }
+        self.shadow = new_shadow(self.state);
+        self.separation.clear();
+        self.compare_state = self.state;
+    }
+
+    /// Re-release the pendulum from its current pose by zeroing both angular velocities. With
+    /// `remember` the pose also becomes the initial state, so R returns to it.
+    fn freeze(&mut self, remember: bool) {
+        self.hold_still();
+        if remember {
+            let pose = release_pose(&self.state);
+            self.initial_state = pose;
+            self.theta1_text = format_angle(pose.θ1, self.use_degrees);
+            self.theta2_text = format_angle(pose.θ2, self.use_degrees);
+        }
+    }
+
+    /// Flash the outer bob and log it each time it comes within CROSSING_DISTANCE of the inner
+    /// arm
+    fn check_crossing(&mut self) {
+        let crossing =
+            self.system.enabled2 && self.system.arm_clearance(&self.state) < CROSSING_DISTANCE;
+        if crossing && !self.crossing {
+            println!("Arms crossed at t = {:.3} s", self.state.t);
+            self.crossing_flash = CROSSING_FLASH;
+        }
+        self.crossing = crossing;
+    }
+
+    /// Switch to the random parameters and release angles generated from `seed`, and restart
+    fn randomize(&mut self, seed: u64) {
+        let (system, state) = random_configuration(seed, &self.system);
+        self.system = system;
+        self.initial_state = state;
+        self.theta1_text = format_angle(state.θ1, self.use_degrees);
+        self.theta2_text = format_angle(state.θ2, self.use_degrees);
+        self.preset = None;
+        self.seed = Some(seed);
+        self.seed_text = format_seed(self.seed);
+        self.reset();
+    }
+
+    /// Turn the arms by the given degrees counter-clockwise and hold them there, like dragging
+    /// the bobs
+    fn nudge(&mut self, θ1_degrees: f64, θ2_degrees: f64) {
+        self.state.θ1 += θ1_degrees.to_radians();
+        self.state.θ2 += θ2_degrees.to_radians();
+        self.hold_still();
+    }
+
+    /// Replace the ensemble with fresh copies of the current state
+    fn respawn_ensemble(&mut self) {
+        self.ensemble = ensemble(
+            self.state,
+            ENSEMBLE_SIZES[self.ensemble_size],
+            ENSEMBLE_SPREADS[self.ensemble_spread],
+        );
+    }
+
+    /// Record the current pendulum locations in the trails, dropping the oldest points beyond
+    /// the capacity
+    fn push_trail(&mut self) {
+        let top = self.pivot + self.top_pendulum_loc();
+        let mut tip = top;
+        if self.system.enabled2 {
+            tip += self.bottom_pendulum_loc();
+        }
+        let speed = self.system.tip_speed(&self.state);
+        push_bounded(
+            &mut self.trail,
+            (tip, speed, self.state.t),
+            self.trail_capacity,
+        );
+        push_bounded(&mut self.inner_trail, top, self.trail_capacity);
+    }
+
+    /// Count a frame towards the trail decimation, recording the trails on every `trail_every`th
+    /// one
+    fn decimate_trail(&mut self) {
+        self.trail_frames += 1;
+        if self.trail_frames >= self.trail_every.max(1) {
+            self.trail_frames = 0;
+            self.push_trail();
+        }
+    }
+
+    /// Record the current state in the phase portraits and the separation from the shadow run,
+    /// dropping the oldest points beyond their capacities
+    fn push_phase(&mut self) {
+        let (p1, p2) = phase_points(&self.state);
+        push_bounded(&mut self.phase1, p1, PHASE_CAPACITY);
+        push_bounded(&mut self.phase2, p2, PHASE_CAPACITY);
+        let separation = log_separation(&self.state, &self.shadow);
+        push_bounded(
+            &mut self.separation,
+            (self.state.t, separation),
+            SEPARATION_CAPACITY,
+        );
+    }
+
+    /// Zoom to `len_scale` pixels per meter, clamped to the slider range, keeping the pivot
+    /// where it is on screen
+    fn set_len_scale(&mut self, len_scale: f64) {
+        self.zoom_at(len_scale, self.pivot + self.view.camera);
+    }
+
+    /// Zoom to `len_scale` pixels per meter like `View::zoom_at`, scaling the trails about the
+    /// pivot along with the pendulum
+    fn zoom_at(&mut self, len_scale: f64, anchor: Vec2) {
+        let k = self.view.zoom_at(len_scale, self.pivot, anchor);
+        self.rescale_trails(k);
+    }
+
+    /// Go back to the default view, leaving the physics alone
+    fn reset_view(&mut self) {
+        let k = self.view.reset(self.pivot);
+        self.rescale_trails(k);
+    }
+
+    /// Scale the trails by `k` about the pivot after a zoom
+    fn rescale_trails(&mut self, k: f32) {
+        let pivot = self.pivot;
+        for (p, _, _) in &mut self.trail {
+            *p = pivot + (*p - pivot) * k;
+        }
+        for p in &mut self.inner_trail {
+            *p = pivot + (*p - pivot) * k;
+        }
+        // Counts can't be rescaled like points, start over at the new zoom
+        self.heatmap.clear();
+    }
+
+    /// Line weight of the trails, half the arms' but never thinner than a pixel
+    fn trail_weight(&self) -> f32 {
+        (self.arm_weight / 2.).max(1.)
+    }
+
+    /// Radius of a bob of `mass` at the current zoom
+    fn bob_radius(&self, mass: f64) -> f32 {
+        mass_to_size(mass, BOB_RADIUS * self.view.len_scale, self.window_rect)
     }
 
     /// Get the location of the top pendulum (relative to the pivot)
     fn top_pendulum_loc(&self) -> Vec2 {
-        let (s, c) = self.state.θ1.sin_cos();
-        Vec2::new(
-            (s * self.system.l1 * LEN_SCALE) as f32,
-            (c * self.system.l1 * LEN_SCALE) as f32,
-        )
+        self.top_loc(&self.state)
     }
 
     /// Get the location of the bottom pendulum (relative to the top pendulum)
     fn bottom_pendulum_loc(&self) -> Vec2 {
-        let (s, c) = self.state.θ2.sin_cos();
-        Vec2::new(
-            (s * self.system.l2 * LEN_SCALE) as f32,
-            (c * self.system.l2 * LEN_SCALE) as f32,
-        )
+        self.bottom_loc(&self.state)
+    }
+
+    /// Get the location of the top pendulum in `state` (relative to the pivot)
+    fn top_loc(&self, state: &DoublePendulumState) -> Vec2 {
+        bob_positions(&self.system, state, self.view.len_scale).0
+    }
+
+    /// Get the location of the bottom pendulum in `state` (relative to the top pendulum)
+    fn bottom_loc(&self, state: &DoublePendulumState) -> Vec2 {
+        bob_positions(&self.system, state, self.view.len_scale).1
     }
 }
 
+/// Pixel offsets of the top pendulum from the pivot and of the bottom pendulum from the top one
+/// in `state`, at `len_scale` pixels per meter. Like `DoublePendulumSystem::bob_positions`, x
+/// points right and y up, so hanging straight down is `(0, -l1)` and `(0, -l2)` scaled.
+fn bob_positions(
+    system: &DoublePendulumSystem,
+    state: &DoublePendulumState,
+    len_scale: f64,
+) -> (Vec2, Vec2) {
+    let (top, bottom) = system.bob_positions(state);
+    let to_pixels =
+        |v: nalgebra::Vector2<f64>| Vec2::new((v.x * len_scale) as f32, (v.y * len_scale) as f32);
+    (to_pixels(top), to_pixels(bottom - top))
+}
+
+/// Command line options, parsed before nannou starts since `model` can't take arguments
+static ARGS: OnceLock<cli::Args> = OnceLock::new();
+
 fn main() {
+    let args = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Some(steps) = args.bench {
+        run_bench(steps);
+        return;
+    }
+    if let Some((which, from, to)) = args.sweep {
+        run_sweep(&args, which, from, to);
+        return;
+    }
+    if let Some(size) = args.basin {
+        run_basin(&args, size);
+        return;
+    }
+    ARGS.set(args).unwrap();
     nannou::app(model).update(update).run();
 }
 
+/// Integrate `steps` fixed steps of the default system without any windows and report how long
+/// it took
+fn run_bench(steps: usize) {
+    let system = DoublePendulumSystem::default();
+    let initial = DoublePendulumState::new(2., 2., 0., 0.);
+    let start = Instant::now();
+    let last = system.iter_states(initial, FIXED_DT).nth(steps).unwrap();
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "Integrated {} steps in {:.3} s: {:.0} steps/s, {:.3} µs/step",
+        steps,
+        elapsed,
+        steps as f64 / elapsed,
+        elapsed / steps as f64 * 1e6
+    );
+    println!("Final state: {:?}", last);
+}
+
+/// Run the configured system with `which` swept from `from` to `to` without any windows, and
+/// save the outer bob's path in each run side by side to a PNG
+fn run_sweep(args: &cli::Args, which: Param, from: f64, to: f64) {
+    let (system, initial) = initial_conditions(args);
+    let systems = sweep::configurations(
+        &system,
+        |system, value| which.set(system, value),
+        from,
+        to,
+        args.sweep_count,
+    );
+    let start = Instant::now();
+    let paths = sweep::run_all(&systems, initial, FIXED_DT, args.sweep_seconds);
+    println!(
+        "Ran {} configurations for {} s each in {:.3} s",
+        systems.len(),
+        args.sweep_seconds,
+        start.elapsed().as_secs_f64()
+    );
+    let reach = systems
+        .iter()
+        .map(|(_, system)| system.l1 + system.l2)
+        .fold(0., f64::max);
+    let path = contact_sheet::timestamped_path(which.name());
+    match contact_sheet::render(&paths, reach).save(&path) {
+        Ok(()) => {
+            println!("Saved contact sheet to {}", path.display());
+            // Left to right, then top to bottom
+            let values: Vec<_> = systems.iter().map(|(value, _)| value.to_string()).collect();
+            println!("{} = {}", which.name(), values.join(", "));
+        }
+        Err(e) => eprintln!("Failed to save contact sheet to {}: {}", path.display(), e),
+    }
+}
+
+/// Render a `size` pixel square basin of attraction of the configured system, damped, without
+/// any windows: each pixel is a release at rest from θ1 and θ2 across the full turn, colored by
+/// how many times the outer arm went over the top before settling
+fn run_basin(args: &cli::Args, size: u32) {
+    let (mut system, _) = initial_conditions(args);
+    // Settling is judged by the energy needed to get over the top, which only exists with gravity
+    // pulling down
+    if system.g <= 0. {
+        eprintln!("A basin needs gravity pulling down, got g = {}", system.g);
+        return;
+    }
+    if system.b <= 0. && system.c_drag <= 0. {
+        println!("No damping configured, using b = {}", BASIN_DAMPING);
+        system.b = BASIN_DAMPING;
+    }
+    let start = Instant::now();
+    let flips = basin::basin(
+        &system,
+        size as usize,
+        size as usize,
+        FIXED_DT,
+        BASIN_MAX_TIME,
+    );
+    println!(
+        "Settled {} releases in {:.3} s",
+        flips.len(),
+        start.elapsed().as_secs_f64()
+    );
+    let image = nannou::image::RgbaImage::from_fn(size, size, |x, y| {
+        flip_color(flips[(y * size + x) as usize])
+    });
+    let path = PathBuf::from(format!("basin_{}.png", unix_time()));
+    match image.save(&path) {
+        Ok(()) => println!("Saved basin of attraction to {}", path.display()),
+        Err(e) => eprintln!("Failed to save basin to {}: {}", path.display(), e),
+    }
+}
+
+/// Black for runs that never flipped, then a hue for each number of flips, spread by the golden
+/// angle so neighbouring counts stand apart
+fn flip_color(flips: u32) -> nannou::image::Rgba<u8> {
+    if flips == 0 {
+        return nannou::image::Rgba([0, 0, 0, 255]);
+    }
+    let hue = (flips as f32 * 0.618_034).fract();
+    let c: Srgb = hsv(hue, 0.75, 1.).into();
+    let byte = |v: f32| (v.clamp(0., 1.) * 255.) as u8;
+    nannou::image::Rgba([byte(c.red), byte(c.green), byte(c.blue), 255])
+}
+
+/// The system and initial state from the defaults, then the config file, then the command line
+fn initial_conditions(args: &cli::Args) -> (DoublePendulumSystem, DoublePendulumState) {
+    let system = DoublePendulumSystem::default();
+    let mut initial_state = DoublePendulumState::new(2., 2., 0., 0.);
+    #[cfg(feature = "serde")]
+    let system = {
+        let mut system = system;
+        config::Config::load_or_default(config::PATH).apply(&mut system, &mut initial_state);
+        system
+    };
+    let overrides = [
+        (args.theta1, &mut initial_state.θ1),
+        (args.theta2, &mut initial_state.θ2),
+        (args.omega1, &mut initial_state.ω1),
+        (args.omega2, &mut initial_state.ω2),
+    ];
+    for (value, field) in overrides {
+        if let Some(value) = value {
+            *field = value;
+        }
+    }
+    (system, initial_state)
+}
+
 fn model(app: &App) -> Model {
+    let args = ARGS.get().cloned().unwrap_or_default();
     let main_window = app
         .new_window()
         .title(app.exe_name().unwrap())
-        .size(WIDTH, HEIGHT)
+        .size(args.width, args.height)
         .view(view)
         .key_pressed(key_pressed)
+        .mouse_pressed(mouse_pressed)
+        .mouse_moved(mouse_moved)
+        .mouse_released(mouse_released)
+        .mouse_wheel(mouse_wheel)
+        .resized(resized)
         .build()
         .unwrap();
 
     let ui_window = app
         .new_window()
         .title(app.exe_name().unwrap() + " controls")
-        .size(350, 400)
+        .size(
+            CONTROLS_WIDTH + PHASE_PLOT_SIZE as u32 + 20,
+            controls_height(app),
+        )
         .view(ui_view)
-        .event(ui_event)
         .key_pressed(key_pressed)
         .build()
         .unwrap();
@@ -85,148 +970,2593 @@ fn model(app: &App) -> Model {
     let ids = Ids::new(ui.widget_id_generator());
 
     ui.clear_with(color::DARK_CHARCOAL);
-    let mut theme = ui.theme_mut();
+    let theme = ui.theme_mut();
     theme.label_color = color::WHITE;
     theme.shape_color = color::CHARCOAL;
 
-    Model {
-        system: Default::default(),
-        state: DoublePendulumState::new(2., 2., 0., 0.),
+    let (system, initial_state) = initial_conditions(&args);
+    let mut model = Model {
+        log_start: system.clone(),
+        system,
+        state: initial_state,
+        initial_state,
         main_window,
+        window_rect: geom::Rect::from_w_h(args.width as f32, args.height as f32),
         ui,
         ids,
+        fixed_dt: FIXED_DT,
+        substeps: 1,
+        ramp_time: 0.,
+        time_scale: 1.,
+        persistence: 0.,
+        view: View {
+            len_scale: args.scale.clamp(MIN_LEN_SCALE, MAX_LEN_SCALE),
+            ..Default::default()
+        },
+        accumulator: 0.,
+        paused: false,
+        diverged: false,
+        sim_time: 0.,
+        fps: 0.,
+        show_hud: false,
+        trail: VecDeque::with_capacity(TRAIL_CAPACITY + 1),
+        inner_trail: VecDeque::with_capacity(TRAIL_CAPACITY + 1),
+        trails: Trails::Outer,
+        arm1_color: BLUE.into_format(),
+        arm2_color: BLUE.into_format(),
+        bob1_color: RED.into_format(),
+        bob2_color: RED.into_format(),
+        trail_capacity: TRAIL_CAPACITY,
+        trail_every: 1,
+        arm_weight: 1.,
+        trail_frames: 0,
+        trail_threshold: 0.,
+        hue_rate: 0.,
+        tracing: false,
+        max_speed_for_scale: MAX_SPEED_FOR_SCALE,
+        pivot: Vec2::new(0., 100.),
+        dragging: None,
+        theta1_text: format_angle(initial_state.θ1, true),
+        theta2_text: format_angle(initial_state.θ2, true),
+        preset: None,
+        rng: StdRng::from_entropy(),
+        seed: None,
+        seed_text: String::new(),
+        recording: None,
+        lyapunov: new_lyapunov(initial_state),
+        show_poincare: false,
+        show_grid: false,
+        show_com: false,
+        show_state: false,
+        show_initial: false,
+        heatmap: Heatmap::new(
+            Vec2::new(args.width as f32, args.height as f32),
+            HEATMAP_CELL,
+        ),
+        show_heatmap: false,
+        heatmap_decay: false,
+        show_help: false,
+        use_degrees: true,
+        detect_crossings: false,
+        crossing: false,
+        crossing_flash: 0.,
+        poincare_points: Vec::new(),
+        shadow: new_shadow(initial_state),
+        separation: VecDeque::with_capacity(SEPARATION_CAPACITY + 1),
+        phase1: VecDeque::with_capacity(PHASE_CAPACITY + 1),
+        phase2: VecDeque::with_capacity(PHASE_CAPACITY + 1),
+        ensemble: Vec::new(),
+        ensemble_size: 0,
+        ensemble_spread: 0,
+        linear: None,
+        elastic: None,
+        watchdog: Some(EnergyWatchdog::new(ENERGY_DRIFT_THRESHOLD)),
+        capturing: false,
+        frame_number: 0,
+        gif: None,
+        gif_seconds: args.gif_seconds,
+        gif_fps: args.gif_fps,
+        change_log: Vec::new(),
+        replay: None,
+        compare_solver: None,
+        compare_state: initial_state,
+        playback: None,
+        playback_clock: 0.,
+    };
+    if let Some(path) = &args.play {
+        load_playback(&mut model, path);
     }
+    model
 }
 
-fn key_pressed(_app: &App, _model: &mut Model, key: Key) {}
+/// Key bindings listed by the help overlay, in the order `key_pressed` handles them. Keep the
+/// two in sync.
+const KEY_HELP: &[(&str, &str)] = &[
+    ("Space", "Pause / resume"),
+    ("R", "Reset to the initial state"),
+    (
+        "Shift+R",
+        "Replay the parameter changes and drags since the last reset",
+    ),
+    ("T", "Start / stop recording the trajectory to CSV"),
+    (
+        "B",
+        "Play back the last recorded trajectory / simulate again",
+    ),
+    ("O", "Poincaré section"),
+    ("G", "Grid"),
+    ("X", "Randomize the parameters and release angles"),
+    ("Q", "Print the equations of motion as LaTeX"),
+    ("Z", "Ghost of the initial configuration"),
+    ("M", "Center of mass"),
+    ("D", "Flash when the arms cross"),
+    ("F", "Freeze, Shift+F also keeps the pose for resets"),
+    ("C", "Reset the view"),
+    ("F3", "Frame rate and times"),
+    ("F4", "Live angles and angular velocities"),
+    ("N", "Linearized ghost"),
+    ("K", "Ghost with springs for arms"),
+    ("E", "Cycle the ensemble size"),
+    ("W", "Cycle the ensemble spread"),
+    ("S / L", "Save / load a snapshot"),
+    (".", "Single step while paused"),
+    (
+        "Arrows",
+        "Turn arm 1 (left / right) or arm 2 (up / down) while paused",
+    ),
+    ("[ / ]", "Decrease / increase gravity"),
+    ("U", "Flip gravity upside down"),
+    (",", "Pause"),
+    ("P", "Screenshot"),
+    ("V", "Start / stop capturing frames"),
+    ("Shift+V", "Record the next few seconds to a GIF"),
+    ("I", "Cycle the trails"),
+    ("H", "This help"),
+    ("A", "Tip heatmap, Shift+A makes old visits fade"),
+    ("Mouse", "Drag the bobs or the pivot, scroll to zoom"),
+];
 
-fn update(_app: &App, model: &mut Model, update: Update) {
-    model.step(update.since_last.as_secs_f64());
-    //println!("{:?} {:?}", model.state, update.since_last.as_secs_f64());
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::Space => model.paused = !model.paused,
+        Key::R if app.keys.mods.shift() => model.start_replay(),
+        Key::R => model.reset(),
+        Key::T => toggle_recording(model),
+        Key::B => toggle_playback(model),
+        Key::O => model.show_poincare = !model.show_poincare,
+        Key::G => model.show_grid = !model.show_grid,
+        Key::X => {
+            let seed = model.rng.gen();
+            model.randomize(seed);
+        }
+        Key::Q => println!("{}", equations_latex(&model.system)),
+        Key::Z => model.show_initial = !model.show_initial,
+        Key::M => model.show_com = !model.show_com,
+        Key::D => model.detect_crossings = !model.detect_crossings,
+        Key::F => model.freeze(app.keys.mods.shift()),
+        Key::C => model.reset_view(),
+        Key::F3 => model.show_hud = !model.show_hud,
+        Key::F4 => model.show_state = !model.show_state,
+        Key::N => {
+            model.linear = match model.linear {
+                None => Some(model.state),
+                Some(_) => None,
+            }
+        }
+        Key::K => {
+            model.elastic = match model.elastic {
+                None => Some(model.new_elastic()),
+                Some(_) => None,
+            }
+        }
+        Key::E => {
+            model.ensemble_size = (model.ensemble_size + 1) % ENSEMBLE_SIZES.len();
+            model.respawn_ensemble();
+        }
+        Key::W => {
+            model.ensemble_spread = (model.ensemble_spread + 1) % ENSEMBLE_SPREADS.len();
+            model.respawn_ensemble();
+        }
+        #[cfg(feature = "serde")]
+        Key::S => save_snapshot(model),
+        #[cfg(feature = "serde")]
+        Key::L => load_snapshot(model),
+        Key::Period if model.paused => {
+            model.step(model.fixed_dt);
+            model.push_trail();
+            model.push_phase();
+        }
+        Key::Left if model.paused => model.nudge(-NUDGE_DEGREES, 0.),
+        Key::Right if model.paused => model.nudge(NUDGE_DEGREES, 0.),
+        Key::Down if model.paused => model.nudge(0., -NUDGE_DEGREES),
+        Key::Up if model.paused => model.nudge(0., NUDGE_DEGREES),
+        Key::LBracket => model.set_param(Param::G, (model.system.g - G_STEP).max(-MAX_G)),
+        Key::RBracket => model.set_param(Param::G, (model.system.g + G_STEP).min(MAX_G)),
+        Key::U => model.set_param(Param::G, -model.system.g),
+        Key::Comma => model.paused = true,
+        Key::P => save_screenshot(app, model),
+        Key::V if app.keys.mods.shift() => start_gif(model),
+        Key::V => toggle_capturing(model),
+        Key::I => model.trails = model.trails.next(),
+        Key::H => model.show_help = !model.show_help,
+        Key::A if app.keys.mods.shift() => model.heatmap_decay = !model.heatmap_decay,
+        Key::A => model.show_heatmap = !model.show_heatmap,
+        _ => {}
+    }
 }
 
-fn view(app: &App, model: &Model, frame: Frame) {
-    fn mass_to_size(mass: f64) -> f32 {
-        10. + (mass as f32 - 1.) * 2.
+/// Start recording the trajectory, or stop and write what was recorded to a CSV file
+fn toggle_recording(model: &mut Model) {
+    match model.recording.take() {
+        None => model.recording = Some(Vec::new()),
+        Some(rows) => {
+            let path = recording::timestamped_path();
+            match recording::write_csv(&path, &rows) {
+                Ok(()) => println!("Wrote {} rows to {}", rows.len(), path.display()),
+                Err(e) => eprintln!("Failed to write trajectory to {}: {}", path.display(), e),
+            }
+        }
     }
+}
 
-    frame.clear(BLACK);
-    let draw = app.draw();
-    let top = model.top_pendulum_loc();
-    let btm = model.bottom_pendulum_loc();
-    draw.translate(Vec3::new(0., 100., 0.));
-    draw.line().x_y(0., 0.).end(-top).color(BLUE);
-    draw.line().xy(-top).end(-btm).color(BLUE);
-    draw.ellipse()
-        .radius(mass_to_size(model.system.m1))
-        .xy(-top)
-        .color(srgb(1., 0., 0.));
-    draw.ellipse()
-        .radius(mass_to_size(model.system.m2))
-        .xy(-top - btm)
-        .color(srgb(1., 0., 0.));
-    draw.to_frame(app, &frame).unwrap();
+/// Play back the most recently recorded trajectory, or go back to simulating if playing one
+fn toggle_playback(model: &mut Model) {
+    if model.playback.take().is_some() {
+        model.reset();
+        return;
+    }
+    match recording::latest_path() {
+        Ok(Some(path)) => load_playback(model, &path),
+        Ok(None) => eprintln!("No trajectory to play back"),
+        Err(e) => eprintln!("Failed to look for trajectories: {}", e),
+    }
 }
 
-fn ui_event(_app: &App, model: &mut Model, _event: WindowEvent) {
-    const LABEL_WIDTH: f64 = 175.;
-    let ui = &mut model.ui.set_widgets();
+/// Start playing back the trajectory in the CSV file at `path` in place of the simulation
+fn load_playback(model: &mut Model, path: &Path) {
+    match trajectory::read_csv(path) {
+        Ok(samples) => {
+            println!(
+                "Playing back {} samples from {}",
+                samples.len(),
+                path.display()
+            );
+            model.playback = Some(samples);
+            model.reset();
+        }
+        Err(e) => eprintln!("Failed to load trajectory from {}: {}", path.display(), e),
+    }
+}
 
-    // Control panel title
-    widget::Text::new("Double Pendulum")
-        .top_left_with_margin(10.0)
-        .w_h(300.0, 40.0)
-        .font_size(24)
-        .set(model.ids.title, ui);
+/// Capture the next frame of the main window to a PNG named after the time and parameters
+fn save_screenshot(app: &App, model: &Model) {
+    let s = &model.system;
+    let path = PathBuf::from(format!(
+        "screenshot_{}_g{:.2}_m{:.2}-{:.2}_l{:.2}-{:.2}.png",
+        unix_time(),
+        s.g,
+        s.m1,
+        s.m2,
+        s.l1,
+        s.l2
+    ));
+    match app.window(model.main_window) {
+        // Write failures are reported by nannou from its capture thread
+        Some(window) => {
+            window.capture_frame(&path);
+            println!("Saving screenshot to {}", path.display());
+        }
+        None => eprintln!("Failed to save screenshot: main window is gone"),
+    }
+}
 
-    // Gravity label
-    widget::Text::new("Gravity")
-        .down_from(model.ids.title, 15.0)
-        .w_h(LABEL_WIDTH, 30.0)
-        .set(model.ids.g_label, ui);
+/// Start or stop saving every frame, creating the output directory when starting
+fn toggle_capturing(model: &mut Model) {
+    if model.capturing {
+        model.capturing = false;
+        println!("Stopped capturing frames");
+        return;
+    }
+    match std::fs::create_dir_all(FRAMES_DIR) {
+        Ok(()) => {
+            model.capturing = true;
+            println!("Capturing frames to {}", FRAMES_DIR);
+        }
+        Err(e) => eprintln!("Failed to create {}: {}", FRAMES_DIR, e),
+    }
+}
 
-    // Gravity slider
-    for value in widget::Slider::new(model.system.g, 0.0, 20.0)
-        .enabled(true)
-        .right_from(model.ids.g_label, 10.0)
-        .w_h(150.0, 30.0)
-        .label(&format!("{:.4}", model.system.g))
-        .set(model.ids.g, ui)
-    {
-        model.system.g = value;
+/// Start recording the main window to a GIF, unless a recording is already running
+fn start_gif(model: &mut Model) {
+    if model.gif.is_some() {
+        return;
     }
+    match GifRecording::start(model.gif_seconds, model.gif_fps) {
+        Ok(gif) => {
+            model.gif = Some(gif);
+            println!(
+                "Recording {} s to a GIF at {} fps",
+                model.gif_seconds, model.gif_fps
+            );
+        }
+        Err(e) => eprintln!("Failed to start recording a GIF: {}", e),
+    }
+}
 
-    // First pendulum mass label
-    widget::Text::new("Pendulum 1 mass")
-        .down_from(model.ids.g_label, 15.0)
-        .w_h(LABEL_WIDTH, 30.0)
-        .set(model.ids.m1_label, ui);
+/// Let `frame_time` pass for the GIF recording, if any, and once it has covered its whole
+/// length hand the frames over to be encoded in the background
+fn advance_gif(app: &App, model: &mut Model, frame_time: f64) {
+    let Some(gif) = &mut model.gif else {
+        return;
+    };
+    gif.advance(frame_time);
+    if !gif.schedule.is_finished() {
+        return;
+    }
+    let gif = model.gif.take().unwrap();
+    // The encoder reads the captured frames back, so they have to be on disk first
+    if let Some(window) = app.window(model.main_window) {
+        if let Err(e) = window.await_capture_frame_jobs() {
+            eprintln!("Timed out waiting for GIF frames to be written: {:?}", e);
+        }
+    }
+    gif.finish(gif_export::timestamped_path());
+}
 
-    // First pendulum mass slider
-    for value in widget::Slider::new(model.system.m1, 0.1, 100.0)
-        .enabled(true)
-        .skew(8.)
-        .right_from(model.ids.m1_label, 10.0)
-        .w_h(150.0, 30.0)
-        .label(&format!("{:.4}", model.system.m1))
-        .set(model.ids.m1, ui)
-    {
-        model.system.m1 = value;
+/// Path of the `n`th captured frame, padded so the files sort in order
+fn frame_path(n: u64) -> PathBuf {
+    PathBuf::from(FRAMES_DIR).join(format!("frame_{:06}.png", n))
+}
+
+/// Write the current system and state to a new snapshot file
+#[cfg(feature = "serde")]
+fn save_snapshot(model: &Model) {
+    let snapshot = snapshot::Snapshot {
+        system: model.system.clone(),
+        state: model.state,
+    };
+    let path = snapshot::timestamped_path();
+    match snapshot.save_to_path(&path) {
+        Ok(()) => println!("Saved snapshot to {}", path.display()),
+        Err(e) => eprintln!("Failed to save snapshot to {}: {}", path.display(), e),
     }
+}
 
-    // First pendulum length label
-    widget::Text::new("Pendulum 1 length")
-        .down_from(model.ids.m1_label, 15.0)
-        .w_h(LABEL_WIDTH, 30.0)
-        .set(model.ids.l1_label, ui);
+/// Restart from the most recent snapshot file
+#[cfg(feature = "serde")]
+fn load_snapshot(model: &mut Model) {
+    let path = match snapshot::latest_path() {
+        Ok(Some(path)) => path,
+        Ok(None) => {
+            eprintln!("No snapshot to load");
+            return;
+        }
+        Err(e) => {
+            eprintln!("Failed to look for snapshots: {}", e);
+            return;
+        }
+    };
+    match snapshot::Snapshot::load_from_path(&path) {
+        Ok(snapshot) => {
+            model.system = snapshot.system;
+            model.initial_state = snapshot.state;
+            model.theta1_text = format_angle(snapshot.state.θ1, model.use_degrees);
+            model.theta2_text = format_angle(snapshot.state.θ2, model.use_degrees);
+            model.reset();
+            println!("Loaded snapshot from {}", path.display());
+        }
+        Err(e) => eprintln!("Failed to load snapshot from {}: {}", path.display(), e),
+    }
+}
 
-    // First pendulum length slider
-    for value in widget::Slider::new(model.system.l1, 0.5, 5.0)
-        .enabled(true)
-        .right_from(model.ids.l1_label, 10.0)
-        .w_h(150.0, 30.0)
-        .label(&format!("{:.4}", model.system.l1))
-        .set(model.ids.l1, ui)
-    {
-        model.system.l1 = value;
+fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
+    if button != MouseButton::Left {
+        return;
     }
+    let pos = app.mouse.position() - model.view.camera;
+    let top = model.pivot + model.top_pendulum_loc();
+    let btm = top + model.bottom_pendulum_loc();
+    model.dragging =
+        if model.system.enabled2 && hit_test(pos, btm, model.bob_radius(model.system.m2)) {
+            Some(Drag::Bottom)
+        } else if hit_test(pos, top, model.bob_radius(model.system.m1)) {
+            Some(Drag::Top)
+        } else if hit_test(pos, model.pivot, PIVOT_GRAB_RADIUS) {
+            Some(Drag::Pivot)
+        } else {
+            None
+        };
+}
 
-    // Second pendulum mass label
-    widget::Text::new("Pendulum 2 mass")
-        .down_from(model.ids.l1_label, 15.0)
-        .w_h(LABEL_WIDTH, 30.0)
-        .set(model.ids.m2_label, ui);
+fn mouse_moved(_app: &App, model: &mut Model, pos: Point2) {
+    let pos = pos - model.view.camera;
+    match model.dragging {
+        Some(Drag::Pivot) => model.pivot = pos,
+        Some(Drag::Top) => {
+            model.state.θ1 = angle_from_offset(pos - model.pivot);
+            model.hold_still();
+        }
+        Some(Drag::Bottom) => {
+            let top = model.pivot + model.top_pendulum_loc();
+            model.state.θ2 = angle_from_offset(pos - top);
+            model.hold_still();
+        }
+        None => {}
+    }
+}
 
-    // First pendulum mass slider
-    for value in widget::Slider::new(model.system.m2, 0.1, 100.0)
-        .enabled(true)
-        .skew(10.)
-        .right_from(model.ids.m2_label, 10.0)
-        .w_h(150.0, 30.0)
-        .label(&format!("{:.4}", model.system.m2))
-        .set(model.ids.m2, ui)
-    {
-        model.system.m2 = value;
+fn mouse_released(_app: &App, model: &mut Model, button: MouseButton) {
+    if button != MouseButton::Left {
+        return;
     }
+    // The run is held still while a bob is dragged, so only where it was let go matters
+    if let Some(Drag::Top | Drag::Bottom) = model.dragging.take() {
+        model.log(Change::Drag(model.state.θ1, model.state.θ2));
+    }
+}
 
-    // Second pendulum length label
-    widget::Text::new("Pendulum 2 length")
-        .down_from(model.ids.m2_label, 15.0)
-        .w_h(LABEL_WIDTH, 30.0)
-        .set(model.ids.l2_label, ui);
+fn mouse_wheel(app: &App, model: &mut Model, delta: MouseScrollDelta, _phase: TouchPhase) {
+    let lines = match delta {
+        MouseScrollDelta::LineDelta(_, y) => y as f64,
+        // Roughly one line per 20 pixels on touchpads
+        MouseScrollDelta::PixelDelta(pos) => pos.y / 20.,
+    };
+    let len_scale = model.view.len_scale * WHEEL_ZOOM_STEP.powf(lines);
+    model.zoom_at(len_scale, app.mouse.position());
+}
 
-    // Second pendulum length slider
-    for value in widget::Slider::new(model.system.l2, 0.5, 5.0)
-        .enabled(true)
-        .right_from(model.ids.l2_label, 10.0)
-        .w_h(150.0, 30.0)
-        .label(&format!("{:.4}", model.system.l2))
-        .set(model.ids.l2, ui)
-    {
-        model.system.l2 = value;
+/// Zoom and panning of the main window, which the physics knows nothing about
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct View {
+    // Pixels per meter
+    len_scale: f64,
+    // Offset of the whole scene in the main window, from zooming about the cursor
+    camera: Vec2,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            len_scale: LEN_SCALE,
+            camera: Vec2::ZERO,
+        }
     }
 }
 
-fn ui_view(app: &App, model: &Model, frame: Frame) {
-    model.ui.draw_to_frame_if_changed(app, &frame).unwrap();
+impl View {
+    /// Zoom to `len_scale` pixels per meter, clamped to the slider range, moving the camera so
+    /// whatever is under `anchor` (in window coordinates) stays there. Returns the factor the
+    /// scene was scaled by about `pivot`.
+    fn zoom_at(&mut self, len_scale: f64, pivot: Vec2, anchor: Vec2) -> f32 {
+        let len_scale = len_scale.clamp(MIN_LEN_SCALE, MAX_LEN_SCALE);
+        let k = (len_scale / self.len_scale) as f32;
+        self.len_scale = len_scale;
+        self.camera = zoom_camera(self.camera, pivot, anchor, k);
+        k
+    }
+
+    /// Go back to the default zoom with the camera at the origin, returning the factor the
+    /// scene was scaled by about `pivot`
+    fn reset(&mut self, pivot: Vec2) -> f32 {
+        let k = self.zoom_at(LEN_SCALE, pivot, pivot + self.camera);
+        self.camera = Vec2::ZERO;
+        k
+    }
+}
+
+/// Camera offset that keeps `anchor` fixed on screen when everything around `pivot` is scaled
+/// up by `k`. A point at `pivot + d` is drawn at `camera + pivot + d` before and at
+/// `camera' + pivot + k * d` after, so solving for the `d` under the anchor gives the new offset.
+fn zoom_camera(camera: Vec2, pivot: Vec2, anchor: Vec2, k: f32) -> Vec2 {
+    anchor + (camera + pivot - anchor) * k - pivot
+}
+
+/// Whether `pos` lies within `radius` of `target`
+fn hit_test(pos: Vec2, target: Vec2, radius: f32) -> bool {
+    pos.distance_squared(target) <= radius * radius
+}
+
+/// Angle of a pendulum whose bob sits at `offset` from its pivot, the inverse of
+/// `top_pendulum_loc`/`bottom_pendulum_loc`
+fn angle_from_offset(offset: Vec2) -> f64 {
+    (offset.x as f64).atan2(-offset.y as f64)
+}
+
+/// Keep the bob size limit in step with the new window size and start the heatmap over on a grid
+/// covering it
+fn resized(_app: &App, model: &mut Model, size: Vec2) {
+    model.window_rect = geom::Rect::from_wh(size);
+    model.heatmap = Heatmap::new(size, HEATMAP_CELL);
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
+    update_ui(model);
+    let frame_time = update.since_last.as_secs_f64();
+    if frame_time > 0. {
+        model.fps = smooth_fps(model.fps, 1. / frame_time);
+    }
+    if model.capturing {
+        model.frame_number += 1;
+    }
+    advance_gif(app, model, frame_time);
+    model.crossing_flash = (model.crossing_flash - frame_time as f32).max(0.);
+    if !is_running(model.paused, model.dragging) {
+        return;
+    }
+    if model.playback.is_some() {
+        model.advance_playback(frame_time * model.time_scale);
+    } else {
+        let steps = fixed_steps(
+            &mut model.accumulator,
+            update.since_last.as_secs_f64(),
+            model.time_scale,
+            model.fixed_dt,
+        );
+        for _ in 0..steps {
+            model.step(model.fixed_dt);
+        }
+    }
+    // Leave out the start of the run until it gets going, so the transient doesn't clutter
+    // the trail
+    if model.system.tip_speed(&model.state) >= model.trail_threshold {
+        model.tracing = true;
+    }
+    if model.tracing {
+        model.decimate_trail();
+    }
+    model.push_phase();
+    if model.detect_crossings {
+        model.check_crossing();
+    }
+    if model.show_heatmap {
+        let mut tip = model.pivot + model.top_pendulum_loc();
+        if model.system.enabled2 {
+            tip += model.bottom_pendulum_loc();
+        }
+        model.heatmap.add(tip);
+        if model.heatmap_decay && app.elapsed_frames().is_multiple_of(HEATMAP_DECAY_EVERY) {
+            model.heatmap.decay();
+        }
+    }
+    // A played back trajectory needn't have come from the current parameters
+    if let (Some(watchdog), None) = (&mut model.watchdog, &model.playback) {
+        watchdog.check(&model.system, &model.state);
+    }
+    if let Some(rows) = &mut model.recording {
+        rows.push(TrajectoryRow::new(&model.system, &model.state));
+    }
+    //println!("{:?} {:?}", model.state, update.since_last.as_secs_f64());
+}
+
+/// `size` copies of `state`, each one's θ2 offset by one more increment of `spread`
+fn ensemble(state: DoublePendulumState, size: usize, spread: f64) -> Vec<DoublePendulumState> {
+    (1..=size)
+        .map(|i| DoublePendulumState {
+            θ2: state.θ2 + i as f64 * spread,
+            ..state
+        })
+        .collect()
+}
+
+/// Points of `state` in the (θ1, ω1) and (θ2, ω2) phase portraits, with the angles wrapped into
+/// (-π, π] so whole turns don't stretch the plots
+fn phase_points(state: &DoublePendulumState) -> (Vec2, Vec2) {
+    let s = state.wrapped();
+    (
+        Vec2::new(s.θ1 as f32, s.ω1 as f32),
+        Vec2::new(s.θ2 as f32, s.ω2 as f32),
+    )
+}
+
+/// log10 of the phase space distance between `state` and its shadow run, as plotted. Runs that
+/// haven't separated at all bottom out at the smallest positive float instead of going to
+/// minus infinity.
+fn log_separation(state: &DoublePendulumState, shadow: &DoublePendulumState) -> f32 {
+    phase_distance(state, shadow).max(f64::MIN_POSITIVE).log10() as f32
+}
+
+/// Append `item`, dropping the oldest items beyond `capacity`
+fn push_bounded<T>(items: &mut VecDeque<T>, item: T, capacity: usize) {
+    items.push_back(item);
+    while items.len() > capacity {
+        items.pop_front();
+    }
+}
+
+/// Initial state for releasing the pendulum from the pose in `state`, at rest with the angles
+/// wrapped and the clock back at zero
+fn release_pose(state: &DoublePendulumState) -> DoublePendulumState {
+    let pose = state.wrapped();
+    DoublePendulumState::new(pose.θ1, pose.θ2, 0., 0.)
+}
+
+/// Whether the simulation moves on this frame, which it doesn't while paused or while a bob is
+/// being positioned. Dragging the pivot only moves where the pendulum is drawn, so it keeps
+/// going.
+fn is_running(paused: bool, dragging: Option<Drag>) -> bool {
+    !paused && !matches!(dragging, Some(Drag::Top | Drag::Bottom))
+}
+
+/// Move `state` on to `next`, the result of a step of `t`, and count the step towards the
+/// simulated time. A step that diverged (`None`) keeps the last good state rather than letting
+/// a NaN wipe out the run, and returns `false`.
+fn take_step(
+    state: &mut DoublePendulumState,
+    sim_time: &mut f64,
+    next: Option<DoublePendulumState>,
+    t: f64,
+) -> bool {
+    match next {
+        Some(next) => {
+            *state = next;
+            *sim_time += t;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Exponential moving average of the frame rate, so the HUD doesn't flicker
+fn smooth_fps(fps: f64, sample: f64) -> f64 {
+    if fps == 0. {
+        sample
+    } else {
+        fps + (sample - fps) * FPS_SMOOTHING
+    }
+}
+
+/// Add `delta` of wall time sped up by `time_scale` to the accumulator and take out as many
+/// whole `fixed_dt` steps as fit, at most `MAX_SUBSTEPS`. The remainder is carried over to the
+/// next frame, except when the clamp kicks in where the excess is dropped so a long stall
+/// doesn't snowball.
+fn fixed_steps(accumulator: &mut f64, delta: f64, time_scale: f64, fixed_dt: f64) -> usize {
+    *accumulator += delta * time_scale;
+    let steps = (*accumulator / fixed_dt).floor() as usize;
+    if steps > MAX_SUBSTEPS {
+        *accumulator = 0.;
+        MAX_SUBSTEPS
+    } else {
+        *accumulator -= steps as f64 * fixed_dt;
+        steps
+    }
+}
+
+/// Downward gravity, masses and lengths drawn from the slider ranges, and a release from rest at
+/// angles up to MAX_RANDOM_ANGLE, all determined by `seed`. Everything else is kept from `base`.
+fn random_configuration(
+    seed: u64,
+    base: &DoublePendulumSystem,
+) -> (DoublePendulumSystem, DoublePendulumState) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let system = DoublePendulumSystem {
+        g: rng.gen_range(0.0..=MAX_G),
+        m1: rng.gen_range(MIN_MASS..=MAX_MASS),
+        m2: rng.gen_range(MIN_MASS..=MAX_MASS),
+        l1: rng.gen_range(MIN_LENGTH..=MAX_LENGTH),
+        l2: rng.gen_range(MIN_LENGTH..=MAX_LENGTH),
+        ..base.clone()
+    };
+    let state = DoublePendulumState::new(
+        rng.gen_range(-MAX_RANDOM_ANGLE..=MAX_RANDOM_ANGLE),
+        rng.gen_range(-MAX_RANDOM_ANGLE..=MAX_RANDOM_ANGLE),
+        0.,
+        0.,
+    );
+    (system, state)
+}
+
+/// Substeps for a step at simulated time `t` of a run with a `ramp_time` slow start. The
+/// violent opening of a release from extreme angles gets RAMP_FACTOR times the `base` substeps,
+/// falling off linearly to just `base` once `ramp_time` has passed.
+fn ramp_substeps(base: usize, t: f64, ramp_time: f64) -> usize {
+    if ramp_time <= 0. || t >= ramp_time {
+        return base;
+    }
+    let factor = 1. + (RAMP_FACTOR - 1.) * (1. - t.max(0.) / ramp_time);
+    (base as f64 * factor).ceil() as usize
+}
+
+/// Start of a shadow run, `state` nudged by LYAPUNOV_EPSILON in θ2
+fn new_shadow(state: DoublePendulumState) -> DoublePendulumState {
+    DoublePendulumState {
+        θ2: state.θ2 + LYAPUNOV_EPSILON,
+        ..state
+    }
+}
+
+fn new_lyapunov(state: DoublePendulumState) -> LyapunovEstimator {
+    LyapunovEstimator::new(state, LYAPUNOV_EPSILON, LYAPUNOV_RENORMALIZE_EVERY)
+}
+
+/// Seconds since the Unix epoch, used to name output files
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Milliseconds since the Unix epoch, for naming output files that can be written more than once
+/// a second
+#[cfg(feature = "serde")]
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// The file in the working directory named `prefix`, a number and `extension` with the highest
+/// number, which for files named after `unix_time` or `unix_time_ms` is the most recent one
+fn latest_stamped_path(prefix: &str, extension: &str) -> std::io::Result<Option<PathBuf>> {
+    let mut stamped = Vec::new();
+    for entry in std::fs::read_dir(".")? {
+        let path = entry?.path();
+        let stamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(prefix))
+            .and_then(|name| name.strip_suffix(extension))
+            .and_then(|stamp| stamp.parse::<u64>().ok());
+        if let Some(stamp) = stamp {
+            stamped.push((stamp, path));
+        }
+    }
+    Ok(stamped
+        .into_iter()
+        .max_by_key(|&(stamp, _)| stamp)
+        .map(|(_, path)| path))
+}
+
+/// Radius in pixels of a bob of `mass`, where a unit mass has radius `base`, kept to a size that
+/// fits in `window`
+fn mass_to_size(mass: f64, base: f64, window: geom::Rect) -> f32 {
+    let max = MAX_BOB_FRACTION * window.w().min(window.h());
+    ((base * mass.cbrt()) as f32).clamp(MIN_BOB_RADIUS, max)
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    // The frame keeps its contents between redraws, so instead of clearing it old drawings can
+    // be faded out by painting translucent black over them. The very first frame has nothing
+    // to keep and is always cleared.
+    if model.persistence <= 0. || frame.nth() == 0 {
+        frame.clear(BLACK);
+    } else if model.persistence < 1. {
+        draw.rect()
+            .wh(frame.rect().wh())
+            .color(srgba(0., 0., 0., 1. - model.persistence));
+    }
+    if model.show_poincare {
+        draw_poincare(&draw, model, frame.rect());
+    } else {
+        let scene = draw.translate(model.view.camera.extend(0.));
+        if model.show_heatmap {
+            let image = nannou::image::DynamicImage::ImageRgba8(model.heatmap.to_image());
+            let texture = wgpu::Texture::from_image(app, &image);
+            scene.texture(&texture).wh(model.heatmap.size());
+        }
+        if model.show_grid {
+            draw_grid(&scene, model, frame.rect().shift(-model.view.camera));
+        }
+        draw_pendulum(&scene, model);
+    }
+    if model.show_hud {
+        let r = frame.rect();
+        draw.text(&format!(
+            "{:.0} fps\nwall {:.1} s\nsim {:.1} s",
+            model.fps, app.time, model.sim_time
+        ))
+        .xy(r.top_left() + Vec2::new(110., -40.))
+        .wh(Vec2::new(200., 60.))
+        .left_justify()
+        .align_text_top()
+        .color(GRAY);
+    }
+    if model.show_state {
+        let r = frame.rect();
+        draw.text(&format_state(&model.state, model.use_degrees))
+            .xy(r.bottom_left() + Vec2::new(110., 50.))
+            .wh(Vec2::new(200., 80.))
+            .left_justify()
+            .align_text_bottom()
+            .color(GRAY);
+    }
+    if model.diverged {
+        draw.text("Simulation diverged, press R to reset")
+            .x_y(0., frame.rect().top() - 60.)
+            .w(frame.rect().w())
+            .font_size(20)
+            .color(RED);
+    }
+    if model.paused {
+        draw.text("PAUSED")
+            .x_y(0., frame.rect().top() - 30.)
+            .font_size(24)
+            .color(WHITE);
+    }
+    if model.show_help {
+        draw_help(&draw, frame.rect());
+    }
+    if let Some(window) = app.window(model.main_window) {
+        if model.capturing {
+            window.capture_frame(frame_path(model.frame_number));
+        }
+        if let Some(path) = model.gif.as_ref().and_then(GifRecording::pending) {
+            window.capture_frame(path);
+        }
+    }
+    draw.to_frame(app, &frame).unwrap();
+}
+
+fn draw_pendulum(draw: &Draw, model: &Model) {
+    let top = model.top_pendulum_loc();
+    let btm = model.bottom_pendulum_loc();
+    if model.trails.inner() && model.inner_trail.len() > 1 {
+        // Dim gray, fading out like the outer trail
+        let len = model.inner_trail.len() as f32;
+        draw.polyline().weight(model.trail_weight()).points_colored(
+            model
+                .inner_trail
+                .iter()
+                .enumerate()
+                .map(|(i, &p)| (p, srgba(0.6, 0.6, 0.6, 0.5 * (i + 1) as f32 / len))),
+        );
+    }
+    if model.trails.outer() && model.trail.len() > 1 {
+        // Color by speed, or sweep the hue with time, and fade out from the newest point to the
+        // oldest one
+        let len = model.trail.len() as f32;
+        draw.polyline().weight(model.trail_weight()).points_colored(
+            model.trail.iter().enumerate().map(|(i, &(p, speed, t))| {
+                let c = if model.hue_rate > 0. {
+                    hsv(time_to_hue(t, model.hue_rate) / 360., 1., 1.).into()
+                } else {
+                    speed_to_color(speed, model.max_speed_for_scale)
+                };
+                (p, srgba(c.red, c.green, c.blue, (i + 1) as f32 / len))
+            }),
+        );
+    }
+    let pendulum = draw.translate(model.pivot.extend(0.));
+    if model.system.pinned {
+        // The path the inner bob is driven around
+        pendulum
+            .ellipse()
+            .radius((model.system.pin_radius * model.view.len_scale) as f32)
+            .no_fill()
+            .stroke(srgba(1., 1., 1., 0.3))
+            .stroke_weight(1.);
+    }
+    let len = model.ensemble.len() as f32;
+    for (i, state) in model.ensemble.iter().enumerate() {
+        let top = model.top_loc(state);
+        let btm = model.bottom_loc(state);
+        let color = hsva(i as f32 / len, 1., 1., 0.6);
+        pendulum.line().start(Vec2::ZERO).end(top).color(color);
+        if model.system.enabled2 {
+            pendulum.line().start(top).end(top + btm).color(color);
+        }
+    }
+    if model.show_initial {
+        draw_ghost(
+            &pendulum,
+            model,
+            &model.initial_state,
+            srgba(1., 1., 1., 0.2),
+        );
+    }
+    if let Some(linear) = &model.linear {
+        draw_ghost(&pendulum, model, linear, srgba(1., 1., 1., 0.5));
+    }
+    if let Some(elastic) = &model.elastic {
+        draw_springs(&pendulum, model, elastic, srgba(0., 1., 1., 0.6));
+    }
+    if let Some(solver) = model.compare_solver {
+        let color = srgba(1., 0., 1., 0.7);
+        draw_ghost(&pendulum, model, &model.compare_state, color);
+        let tip = model.top_loc(&model.compare_state) + model.bottom_loc(&model.compare_state);
+        let offset = Vec2::new(0., 25.);
+        pendulum.text(solver.name()).xy(tip + offset).color(color);
+        pendulum
+            .text(model.system.solver.name())
+            .xy(top + btm + offset)
+            .color(WHITE);
+    }
+    let bend = model.state.relative_angle().abs() / PI as f64;
+    let bobs = [
+        (
+            top,
+            model.bob_radius(model.system.m1),
+            model.arm1_color,
+            model.bob1_color,
+        ),
+        (
+            top + btm,
+            model.bob_radius(model.system.m2),
+            bend_color(model.arm2_color, bend),
+            model.bob2_color,
+        ),
+    ];
+    let arms = if model.system.enabled2 { 2 } else { 1 };
+    draw_chain(&pendulum, &bobs[..arms], model.arm_weight);
+    if model.crossing_flash > 0. {
+        pendulum
+            .ellipse()
+            .radius(model.bob_radius(model.system.m2) * 2.)
+            .xy(top + btm)
+            .no_fill()
+            .stroke_weight(3.)
+            .stroke(srgba(1., 0., 0., model.crossing_flash / CROSSING_FLASH));
+    }
+    if model.show_com {
+        let com = model.system.center_of_mass(&model.state) * model.view.len_scale;
+        let com = Vec2::new(com.x as f32, com.y as f32);
+        for arm in [Vec2::new(6., 6.), Vec2::new(6., -6.)] {
+            pendulum
+                .line()
+                .start(com - arm)
+                .end(com + arm)
+                .weight(2.)
+                .color(YELLOW);
+        }
+    }
+}
+
+/// KEY_HELP in two columns over a dimmed `rect`
+fn draw_help(draw: &Draw, rect: geom::Rect) {
+    draw.rect().wh(rect.wh()).color(srgba(0., 0., 0., 0.75));
+    let (keys, actions): (Vec<_>, Vec<_>) = KEY_HELP.iter().copied().unzip();
+    let h = KEY_HELP.len() as f32 * 20.;
+    draw.text(&keys.join("\n"))
+        .x_y(-200., 0.)
+        .wh(Vec2::new(100., h))
+        .right_justify()
+        .color(WHITE);
+    draw.text(&actions.join("\n"))
+        .x_y(90., 0.)
+        .wh(Vec2::new(440., h))
+        .left_justify()
+        .color(WHITE);
+}
+
+/// Arms and bobs of `state` in a single translucent color, relative to the pivot
+fn draw_ghost(draw: &Draw, model: &Model, state: &DoublePendulumState, color: Srgba) {
+    let top = model.top_loc(state);
+    let btm = model.bottom_loc(state);
+    let radius = model.bob_radius(model.system.m1);
+    draw.line().start(Vec2::ZERO).end(top).color(color);
+    draw.ellipse().radius(radius).xy(top).color(color);
+    if model.system.enabled2 {
+        let radius = model.bob_radius(model.system.m2);
+        draw.line().start(top).end(top + btm).color(color);
+        draw.ellipse().radius(radius).xy(top + btm).color(color);
+    }
+}
+
+/// The elastic pendulum in `state` as zigzag springs ending in bobs, relative to the pivot. The
+/// coils spread out as the springs stretch, and the stretched lengths are written beside them.
+fn draw_springs(draw: &Draw, model: &Model, state: &ElasticDoublePendulumState, color: Srgba) {
+    let system = ElasticDoublePendulumSystem::from_rigid(&model.system, SPRING_STIFFNESS);
+    let (top, bottom) = system.bob_positions(state);
+    let to_pixels = |v: nalgebra::Vector2<f64>| {
+        Vec2::new(
+            (v.x * model.view.len_scale) as f32,
+            (v.y * model.view.len_scale) as f32,
+        )
+    };
+    let (top, bottom) = (to_pixels(top), to_pixels(bottom));
+    let width = (BOB_RADIUS * model.view.len_scale) as f32 / 2.;
+    let mut springs = vec![(Vec2::ZERO, top, state.r1, model.system.m1)];
+    if model.system.enabled2 {
+        springs.push((top, bottom, state.r2, model.system.m2));
+    }
+    for (start, end, length, mass) in springs {
+        draw.polyline()
+            .weight(model.trail_weight())
+            .points(spring_points(start, end, SPRING_COILS, width))
+            .color(color);
+        draw.ellipse()
+            .radius(model.bob_radius(mass))
+            .xy(end)
+            .color(color);
+        draw.text(&format!("{:.3} m", length))
+            .xy(end + Vec2::new(40., 10.))
+            .color(color);
+    }
+}
+
+/// Zigzag from `start` to `end` with `coils` teeth sticking out `width` pixels either side
+fn spring_points(start: Vec2, end: Vec2, coils: usize, width: f32) -> Vec<Vec2> {
+    let normal = (end - start).perp().normalize_or_zero() * width;
+    let teeth = 2 * coils;
+    let mut points = vec![start];
+    points.extend((0..teeth).map(|i| {
+        let side = if i % 2 == 0 { 1. } else { -1. };
+        start.lerp(end, (i as f32 + 0.5) / teeth as f32) + normal * side
+    }));
+    points.push(end);
+    points
+}
+
+/// Axes through the pivot and circles showing how far each bob can reach, labelled in meters
+fn draw_grid(draw: &Draw, model: &Model, rect: geom::Rect) {
+    let color = srgba(1., 1., 1., 0.25);
+    let pivot = model.pivot;
+    draw.line()
+        .start(Vec2::new(rect.left(), pivot.y))
+        .end(Vec2::new(rect.right(), pivot.y))
+        .color(color);
+    draw.line()
+        .start(Vec2::new(pivot.x, rect.bottom()))
+        .end(Vec2::new(pivot.x, rect.top()))
+        .color(color);
+    let (l1, l2) = (model.system.l1, model.system.l2);
+    let radii = if model.system.enabled2 {
+        // The outer bob stays between the inner and outer circles
+        vec![l1, (l1 - l2).abs(), l1 + l2]
+    } else {
+        vec![l1]
+    };
+    for r in radii.into_iter().filter(|&r| r > 0.) {
+        let px = (r * model.view.len_scale) as f32;
+        draw.ellipse()
+            .xy(pivot)
+            .radius(px)
+            .no_fill()
+            .stroke(color)
+            .stroke_weight(1.);
+        draw.text(&format!("{:.2} m", r))
+            .xy(pivot + Vec2::new(px + 25., 8.))
+            .color(color);
+    }
+}
+
+/// Draw a chain of links from the pivot at the origin through each
+/// `(location, radius, link color, bob color)` bob, where the link is the one leading to the bob.
+/// The links are `weight` pixels thick with round caps so the joints look clean.
+fn draw_chain(draw: &Draw, bobs: &[(Vec2, f32, Srgb, Srgb)], weight: f32) {
+    draw.ellipse().radius(3.).x_y(0., 0.).color(GRAY);
+    let mut prev = Vec2::ZERO;
+    for &(loc, _, color, _) in bobs {
+        draw.line()
+            .start(prev)
+            .end(loc)
+            .weight(weight)
+            .caps_round()
+            .color(color);
+        prev = loc;
+    }
+    for &(loc, radius, _, color) in bobs {
+        draw.ellipse().radius(radius).xy(loc).color(color);
+    }
+}
+
+/// Map a speed onto a color going from blue when still to red at `max_speed`
+fn speed_to_color(speed: f64, max_speed: f64) -> Srgb {
+    let x = (speed / max_speed).clamp(0., 1.) as f32;
+    hsv(2. / 3. * (1. - x), 1., 1.).into()
+}
+
+/// Hue in degrees in [0, 360) of a trail point recorded at simulated time `t`, turning at
+/// `hue_rate` degrees per second
+fn time_to_hue(t: f64, hue_rate: f64) -> f32 {
+    (t * hue_rate).rem_euclid(360.) as f32
+}
+
+/// Color of the outer link for a joint bent by `bend` (0 straight, 1 folded back), turning the
+/// hue of `color` towards red as it bends
+fn bend_color(color: Srgb, bend: f64) -> Srgb {
+    let x = bend.clamp(0., 1.) as f32;
+    let mut hsv = Hsv::from(color);
+    hsv.hue = (hsv.hue.to_positive_degrees() * (1. - x)).into();
+    hsv.into()
+}
+
+/// Scatter plot of the Poincaré section, θ2 across and ω2 up, scaled to fit the points and
+/// the window `rect`
+fn draw_poincare(draw: &Draw, model: &Model, rect: geom::Rect) {
+    let (w, h) = (rect.w() / 2. - 20., rect.h() / 2. - 20.);
+    draw.line()
+        .start(Vec2::new(-w, 0.))
+        .end(Vec2::new(w, 0.))
+        .color(GRAY);
+    draw.line()
+        .start(Vec2::new(0., -h))
+        .end(Vec2::new(0., h))
+        .color(GRAY);
+    draw.text("θ2").x_y(w - 20., 15.).color(GRAY);
+    draw.text("ω2").x_y(20., h - 10.).color(GRAY);
+    let ω_max = model
+        .poincare_points
+        .iter()
+        .fold(1e-3f32, |max, p| max.max(p.y.abs()));
+    let scale = Vec2::new(w / PI, h / ω_max);
+    for p in &model.poincare_points {
+        draw.ellipse().radius(1.5).xy(*p * scale).color(WHITE);
+    }
+}
+
+fn update_ui(model: &mut Model) {
+    const LABEL_WIDTH: f64 = 175.;
+    let before = model.system.clone();
+    let mut ui_cell = model.ui.set_widgets();
+    let ui = &mut ui_cell;
+    // Resetting, zooming and randomizing need the whole model, so they're deferred until the
+    // widgets are set
+    let mut reset = false;
+    let mut len_scale = None;
+    let mut seed = None;
+
+    // Everything scrolls together when the window is too short to show it all
+    widget::Canvas::new()
+        .wh_of(ui.window)
+        .middle_of(ui.window)
+        .scroll_kids_vertically()
+        .color(color::DARK_CHARCOAL)
+        .border(0.0)
+        .set(model.ids.controls, ui);
+
+    // Control panel title
+    widget::Text::new("Double Pendulum")
+        .top_left_with_margin_on(model.ids.controls, 10.0)
+        .w_h(300.0, 40.0)
+        .font_size(24)
+        .set(model.ids.title, ui);
+
+    // Preset label
+    widget::Text::new("Preset")
+        .down_from(model.ids.title, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.preset_label, ui);
+
+    // Preset drop down
+    let presets = presets::presets();
+    let names: Vec<&str> = presets.iter().map(|(name, _, _)| *name).collect();
+    if let Some(index) = widget::DropDownList::new(&names, model.preset)
+        .right_from(model.ids.preset_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label("Choose...")
+        .set(model.ids.preset, ui)
+    {
+        let (_, state, system) = presets.into_iter().nth(index).unwrap();
+        model.preset = Some(index);
+        model.seed = None;
+        model.seed_text.clear();
+        // Presets don't pick an integrator
+        model.system = DoublePendulumSystem {
+            solver: model.system.solver,
+            tol: model.system.tol,
+            energy_correct: model.system.energy_correct,
+            ..system
+        };
+        model.initial_state = state;
+        model.theta1_text = format_angle(state.θ1, model.use_degrees);
+        model.theta2_text = format_angle(state.θ2, model.use_degrees);
+        reset = true;
+    }
+
+    // Solver label
+    widget::Text::new("Solver")
+        .down_from(model.ids.preset_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.solver_label, ui);
+
+    // Solver drop down, switching keeps the current state
+    let names: Vec<&str> = Solver::ALL.iter().map(|s| s.name()).collect();
+    let selected = Solver::ALL.iter().position(|&s| s == model.system.solver);
+    if let Some(index) = widget::DropDownList::new(&names, selected)
+        .right_from(model.ids.solver_label, 10.0)
+        .w_h(150.0, 30.0)
+        .set(model.ids.solver, ui)
+    {
+        model.system.solver = Solver::ALL[index];
+    }
+
+    // Tolerance label, only the adaptive solver has one
+    let adaptive = model.system.solver == Solver::Dopri5;
+    let adaptive_color = if adaptive {
+        color::WHITE
+    } else {
+        color::DARK_GREY
+    };
+    widget::Text::new("Tolerance")
+        .down_from(model.ids.solver_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .color(adaptive_color)
+        .set(model.ids.tol_label, ui);
+
+    // Tolerance slider, moving in powers of ten
+    if let Some(value) = widget::Slider::new(
+        model
+            .system
+            .tol
+            .log10()
+            .clamp(MIN_TOL_EXPONENT, MAX_TOL_EXPONENT),
+        MIN_TOL_EXPONENT,
+        MAX_TOL_EXPONENT,
+    )
+    .enabled(adaptive)
+    .right_from(model.ids.tol_label, 10.0)
+    .w_h(150.0, 30.0)
+    .label(&format!("{:.0e}", model.system.tol))
+    .label_color(adaptive_color)
+    .set(model.ids.tol, ui)
+    {
+        model.system.tol = 10f64.powf(value);
+    }
+
+    // Comparison label
+    widget::Text::new("Compare with")
+        .down_from(model.ids.tol_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.compare_label, ui);
+
+    // Comparison drop down, picking a solver starts it from the current state
+    let names: Vec<&str> = std::iter::once("Off")
+        .chain(Solver::ALL.iter().map(|s| s.name()))
+        .collect();
+    let selected = match model.compare_solver {
+        None => Some(0),
+        Some(solver) => Solver::ALL.iter().position(|&s| s == solver).map(|i| i + 1),
+    };
+    if let Some(index) = widget::DropDownList::new(&names, selected)
+        .right_from(model.ids.compare_label, 10.0)
+        .w_h(150.0, 30.0)
+        .set(model.ids.compare, ui)
+    {
+        model.compare_solver = index.checked_sub(1).map(|i| Solver::ALL[i]);
+        model.compare_state = model.state;
+    }
+
+    // Gravity label
+    widget::Text::new("Gravity")
+        .down_from(model.ids.compare_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.g_label, ui);
+
+    // Gravity slider
+    if let Some(value) = widget::Slider::new(model.system.g, -MAX_G, MAX_G)
+        .enabled(true)
+        .right_from(model.ids.g_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.g))
+        .set(model.ids.g, ui)
+    {
+        model.system.g = value;
+    }
+
+    // Gravity preset label
+    widget::Text::new("Gravity of")
+        .down_from(model.ids.g_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.gravity_preset_label, ui);
+
+    // Gravity preset drop down, showing the body whose gravity matches the slider if any
+    let names: Vec<&str> = GRAVITY_PRESETS.iter().map(|(name, _)| *name).collect();
+    let selected = GRAVITY_PRESETS
+        .iter()
+        .position(|&(_, g)| g == model.system.g);
+    if let Some(index) = widget::DropDownList::new(&names, selected)
+        .right_from(model.ids.gravity_preset_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label("Custom")
+        .set(model.ids.gravity_preset, ui)
+    {
+        model.system.g = GRAVITY_PRESETS[index].1;
+    }
+
+    // First pendulum mass label
+    widget::Text::new("Pendulum 1 mass")
+        .down_from(model.ids.gravity_preset_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.m1_label, ui);
+
+    // First pendulum mass slider
+    if let Some(value) = widget::Slider::new(model.system.m1, MIN_MASS, MAX_MASS)
+        .enabled(true)
+        .skew(8.)
+        .right_from(model.ids.m1_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.m1))
+        .set(model.ids.m1, ui)
+    {
+        model.system.m1 = value;
+    }
+
+    // First pendulum length label
+    widget::Text::new("Pendulum 1 length")
+        .down_from(model.ids.m1_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.l1_label, ui);
+
+    // First pendulum length slider
+    if let Some(value) = widget::Slider::new(model.system.l1, MIN_LENGTH, MAX_LENGTH)
+        .enabled(true)
+        .right_from(model.ids.l1_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.l1))
+        .set(model.ids.l1, ui)
+    {
+        model.system.l1 = value;
+    }
+
+    // Second pendulum toggle
+    let enabled2 = model.system.enabled2;
+    for value in widget::Toggle::new(enabled2)
+        .down_from(model.ids.l1_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .label(if enabled2 {
+            "Second arm: on"
+        } else {
+            "Second arm: off"
+        })
+        .set(model.ids.enabled2, ui)
+    {
+        model.system.enabled2 = value;
+    }
+    let enabled2_color = if enabled2 {
+        color::WHITE
+    } else {
+        color::DARK_GREY
+    };
+
+    // Second pendulum mass label
+    widget::Text::new("Pendulum 2 mass")
+        .down_from(model.ids.enabled2, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .color(enabled2_color)
+        .set(model.ids.m2_label, ui);
+
+    // Second pendulum mass slider
+    if let Some(value) = widget::Slider::new(model.system.m2, MIN_MASS, MAX_MASS)
+        .enabled(enabled2)
+        .skew(10.)
+        .right_from(model.ids.m2_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.m2))
+        .label_color(enabled2_color)
+        .set(model.ids.m2, ui)
+    {
+        model.system.m2 = value;
+    }
+
+    // Second pendulum length label
+    widget::Text::new("Pendulum 2 length")
+        .down_from(model.ids.m2_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .color(enabled2_color)
+        .set(model.ids.l2_label, ui);
+
+    // Second pendulum length slider
+    if let Some(value) = widget::Slider::new(model.system.l2, MIN_LENGTH, MAX_LENGTH)
+        .enabled(enabled2)
+        .right_from(model.ids.l2_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.l2))
+        .label_color(enabled2_color)
+        .set(model.ids.l2, ui)
+    {
+        model.system.l2 = value;
+    }
+
+    // Damping label
+    widget::Text::new("Damping")
+        .down_from(model.ids.l2_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.b_label, ui);
+
+    // Damping slider
+    if let Some(value) = widget::Slider::new(model.system.b, 0.0, 2.0)
+        .enabled(true)
+        .right_from(model.ids.b_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.b))
+        .set(model.ids.b, ui)
+    {
+        model.system.b = value;
+    }
+
+    // Air drag label
+    widget::Text::new("Air drag")
+        .down_from(model.ids.b_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.c_drag_label, ui);
+
+    // Air drag slider
+    if let Some(value) = widget::Slider::new(model.system.c_drag, 0.0, 2.0)
+        .enabled(true)
+        .right_from(model.ids.c_drag_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.c_drag))
+        .set(model.ids.c_drag, ui)
+    {
+        model.system.c_drag = value;
+    }
+
+    // Drive amplitude label
+    widget::Text::new("Drive amplitude")
+        .down_from(model.ids.c_drag_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.drive_amp_label, ui);
+
+    // Drive amplitude slider
+    if let Some(value) = widget::Slider::new(model.system.drive_amp, 0.0, 20.0)
+        .enabled(true)
+        .right_from(model.ids.drive_amp_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.drive_amp))
+        .set(model.ids.drive_amp, ui)
+    {
+        model.system.drive_amp = value;
+    }
+
+    // Drive frequency label
+    widget::Text::new("Drive frequency")
+        .down_from(model.ids.drive_amp_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.drive_freq_label, ui);
+
+    // Drive frequency slider
+    if let Some(value) = widget::Slider::new(model.system.drive_freq, 0.0, 10.0)
+        .enabled(true)
+        .right_from(model.ids.drive_freq_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.drive_freq))
+        .set(model.ids.drive_freq, ui)
+    {
+        model.system.drive_freq = value;
+    }
+
+    // Frame rotation label
+    widget::Text::new("Frame rotation")
+        .down_from(model.ids.drive_freq_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.omega_frame_label, ui);
+
+    // Frame rotation slider
+    if let Some(value) = widget::Slider::new(model.system.omega_frame, 0.0, 10.0)
+        .enabled(true)
+        .right_from(model.ids.omega_frame_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.omega_frame))
+        .set(model.ids.omega_frame, ui)
+    {
+        model.system.omega_frame = value;
+    }
+
+    // Pinned inner bob toggle
+    let pinned = model.system.pinned;
+    for value in widget::Toggle::new(pinned)
+        .down_from(model.ids.omega_frame_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .label(if pinned {
+            "Pinned inner bob: on"
+        } else {
+            "Pinned inner bob: off"
+        })
+        .set(model.ids.pinned, ui)
+    {
+        model.system.pinned = value;
+    }
+
+    // Pinned inner bob circle radius slider
+    if let Some(value) = widget::Slider::new(model.system.pin_radius, 0.0, MAX_LENGTH)
+        .enabled(pinned)
+        .right_from(model.ids.pinned, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("r = {:.2} m", model.system.pin_radius))
+        .set(model.ids.pin_radius, ui)
+    {
+        model.system.pin_radius = value;
+    }
+
+    // Pinned inner bob frequency label
+    widget::Text::new("Pin frequency")
+        .down_from(model.ids.pinned, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.pin_freq_label, ui);
+
+    // Pinned inner bob frequency slider
+    if let Some(value) = widget::Slider::new(model.system.pin_freq, -MAX_PIN_FREQ, MAX_PIN_FREQ)
+        .enabled(pinned)
+        .right_from(model.ids.pin_freq_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.4}", model.system.pin_freq))
+        .set(model.ids.pin_freq, ui)
+    {
+        model.system.pin_freq = value;
+    }
+
+    // Time scale label
+    widget::Text::new("Time scale")
+        .down_from(model.ids.pin_freq_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.time_scale_label, ui);
+
+    // Time scale slider
+    if let Some(value) = widget::Slider::new(model.time_scale, 0.05, 5.0)
+        .enabled(true)
+        .right_from(model.ids.time_scale_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.2}x", model.time_scale))
+        .set(model.ids.time_scale, ui)
+    {
+        model.time_scale = value;
+    }
+
+    // Substeps label
+    widget::Text::new("Substeps per frame")
+        .down_from(model.ids.time_scale_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.substeps_label, ui);
+
+    // Substeps slider
+    if let Some(value) =
+        widget::Slider::new(model.substeps as f32, 1.0, MAX_INTEGRATION_SUBSTEPS as f32)
+            .enabled(true)
+            .right_from(model.ids.substeps_label, 10.0)
+            .w_h(150.0, 30.0)
+            .label(&model.substeps.to_string())
+            .set(model.ids.substeps, ui)
+    {
+        model.substeps = value.round() as usize;
+    }
+
+    // Slow start label
+    widget::Text::new("Slow start (s)")
+        .down_from(model.ids.substeps_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.ramp_time_label, ui);
+
+    // Slow start slider
+    if let Some(value) = widget::Slider::new(model.ramp_time, 0.0, MAX_RAMP_TIME)
+        .enabled(true)
+        .right_from(model.ids.ramp_time_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.2}", model.ramp_time))
+        .set(model.ids.ramp_time, ui)
+    {
+        model.ramp_time = value;
+    }
+
+    // Energy correction toggle
+    let energy_correct = model.system.energy_correct;
+    for value in widget::Toggle::new(energy_correct)
+        .down_from(model.ids.ramp_time_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .label(if energy_correct {
+            "Energy correction: on"
+        } else {
+            "Energy correction: off"
+        })
+        .set(model.ids.energy_correct, ui)
+    {
+        model.system.energy_correct = value;
+    }
+
+    // Zoom label
+    widget::Text::new("Zoom (px/m)")
+        .down_from(model.ids.energy_correct, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.len_scale_label, ui);
+
+    // Zoom slider
+    if let Some(value) = widget::Slider::new(model.view.len_scale, MIN_LEN_SCALE, MAX_LEN_SCALE)
+        .enabled(true)
+        .right_from(model.ids.len_scale_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.1}", model.view.len_scale))
+        .set(model.ids.len_scale, ui)
+    {
+        len_scale = Some(value);
+    }
+
+    // Persistence label
+    widget::Text::new("Persistence")
+        .down_from(model.ids.len_scale_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.persistence_label, ui);
+
+    // Persistence slider
+    if let Some(value) = widget::Slider::new(model.persistence, 0.0, 1.0)
+        .enabled(true)
+        .right_from(model.ids.persistence_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.3}", model.persistence))
+        .set(model.ids.persistence, ui)
+    {
+        model.persistence = value;
+    }
+
+    // Trail decimation label
+    widget::Text::new("Trail every N frames")
+        .down_from(model.ids.persistence_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.trail_every_label, ui);
+
+    // Trail decimation slider
+    if let Some(value) = widget::Slider::new(model.trail_every as f32, 1.0, MAX_TRAIL_EVERY as f32)
+        .enabled(true)
+        .right_from(model.ids.trail_every_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&model.trail_every.to_string())
+        .set(model.ids.trail_every, ui)
+    {
+        model.trail_every = (value.round() as usize).max(1);
+    }
+
+    // Trail threshold label
+    widget::Text::new("Trail start speed")
+        .down_from(model.ids.trail_every_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.trail_threshold_label, ui);
+
+    // Trail threshold slider
+    if let Some(value) = widget::Slider::new(model.trail_threshold, 0.0, MAX_SPEED_FOR_SCALE)
+        .enabled(true)
+        .right_from(model.ids.trail_threshold_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.2} m/s", model.trail_threshold))
+        .set(model.ids.trail_threshold, ui)
+    {
+        model.trail_threshold = value;
+    }
+
+    // Trail hue rate label
+    widget::Text::new("Trail hue (°/s)")
+        .down_from(model.ids.trail_threshold_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.hue_rate_label, ui);
+
+    // Trail hue rate slider
+    if let Some(value) = widget::Slider::new(model.hue_rate, 0.0, MAX_HUE_RATE)
+        .enabled(true)
+        .right_from(model.ids.hue_rate_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&if model.hue_rate > 0. {
+            format!("{:.1}", model.hue_rate)
+        } else {
+            "By speed".to_string()
+        })
+        .set(model.ids.hue_rate, ui)
+    {
+        model.hue_rate = value;
+    }
+
+    // Arm thickness label
+    widget::Text::new("Arm thickness")
+        .down_from(model.ids.hue_rate_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.arm_weight_label, ui);
+
+    // Arm thickness slider
+    if let Some(value) = widget::Slider::new(model.arm_weight, 1.0, MAX_ARM_WEIGHT)
+        .enabled(true)
+        .right_from(model.ids.arm_weight_label, 10.0)
+        .w_h(150.0, 30.0)
+        .label(&format!("{:.1} px", model.arm_weight))
+        .set(model.ids.arm_weight, ui)
+    {
+        model.arm_weight = value;
+    }
+
+    // Angle unit toggle, the state stays in radians either way
+    let use_degrees = model.use_degrees;
+    for value in widget::Toggle::new(use_degrees)
+        .down_from(model.ids.arm_weight_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .label(if use_degrees {
+            "Angles: degrees"
+        } else {
+            "Angles: radians"
+        })
+        .set(model.ids.use_degrees, ui)
+    {
+        model.use_degrees = value;
+        model.theta1_text = format_angle(model.initial_state.θ1, value);
+        model.theta2_text = format_angle(model.initial_state.θ2, value);
+    }
+    let unit = if model.use_degrees { "deg" } else { "rad" };
+
+    // First pendulum initial angle label
+    widget::Text::new(&format!("Initial angle 1 ({})", unit))
+        .down_from(model.ids.use_degrees, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.theta1_label, ui);
+
+    // First pendulum initial angle text box
+    for event in widget::TextBox::new(&model.theta1_text)
+        .right_from(model.ids.theta1_label, 10.0)
+        .w_h(150.0, 30.0)
+        .set(model.ids.theta1, ui)
+    {
+        match event {
+            widget::text_box::Event::Update(text) => model.theta1_text = text,
+            widget::text_box::Event::Enter => {
+                if enter_angle(
+                    &mut model.theta1_text,
+                    &mut model.initial_state.θ1,
+                    model.use_degrees,
+                ) && model.paused
+                {
+                    reset = true;
+                }
+            }
+        }
+    }
+
+    // Second pendulum initial angle label
+    widget::Text::new(&format!("Initial angle 2 ({})", unit))
+        .down_from(model.ids.theta1_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.theta2_label, ui);
+
+    // Second pendulum initial angle text box
+    for event in widget::TextBox::new(&model.theta2_text)
+        .right_from(model.ids.theta2_label, 10.0)
+        .w_h(150.0, 30.0)
+        .set(model.ids.theta2, ui)
+    {
+        match event {
+            widget::text_box::Event::Update(text) => model.theta2_text = text,
+            widget::text_box::Event::Enter => {
+                if enter_angle(
+                    &mut model.theta2_text,
+                    &mut model.initial_state.θ2,
+                    model.use_degrees,
+                ) && model.paused
+                {
+                    reset = true;
+                }
+            }
+        }
+    }
+
+    // Seed label
+    widget::Text::new("Random seed (X rolls)")
+        .down_from(model.ids.theta2_label, 15.0)
+        .w_h(LABEL_WIDTH, 30.0)
+        .set(model.ids.seed_label, ui);
+
+    // Seed text box, entering a seed regenerates its configuration
+    for event in widget::TextBox::new(&model.seed_text)
+        .right_from(model.ids.seed_label, 10.0)
+        .w_h(150.0, 30.0)
+        .set(model.ids.seed, ui)
+    {
+        match event {
+            widget::text_box::Event::Update(text) => model.seed_text = text,
+            widget::text_box::Event::Enter => match model.seed_text.trim().parse::<u64>() {
+                Ok(value) => seed = Some(value),
+                Err(_) => model.seed_text = format_seed(model.seed),
+            },
+        }
+    }
+
+    // Energy readouts
+    widget::Text::new(&format!(
+        "Total energy: {:.3}",
+        model.system.total_energy(&model.state)
+    ))
+    .down_from(model.ids.seed_label, 25.0)
+    .w_h(300.0, 20.0)
+    .set(model.ids.total_energy, ui);
+
+    widget::Text::new(&format!(
+        "Kinetic energy: {:.3}",
+        model.system.kinetic_energy(&model.state)
+    ))
+    .down_from(model.ids.total_energy, 10.0)
+    .w_h(300.0, 20.0)
+    .set(model.ids.kinetic_energy, ui);
+
+    widget::Text::new(&format!(
+        "Potential energy: {:.3}",
+        model.system.potential_energy(&model.state)
+    ))
+    .down_from(model.ids.kinetic_energy, 10.0)
+    .w_h(300.0, 20.0)
+    .set(model.ids.potential_energy, ui);
+
+    widget::Text::new(&format!(
+        "Angular momentum: {:.3}",
+        model.system.angular_momentum(&model.state)
+    ))
+    .down_from(model.ids.potential_energy, 10.0)
+    .w_h(300.0, 20.0)
+    .set(model.ids.angular_momentum, ui);
+
+    // Lyapunov exponent estimate
+    widget::Text::new(&format!(
+        "Lyapunov exponent: {:.3}",
+        model.lyapunov.current_estimate()
+    ))
+    .down_from(model.ids.angular_momentum, 10.0)
+    .w_h(300.0, 20.0)
+    .set(model.ids.lyapunov, ui);
+
+    // Bend of the joint between the arms
+    widget::Text::new(&format!(
+        "Relative angle: {}{}",
+        format_angle(model.state.relative_angle(), model.use_degrees),
+        angle_unit(model.use_degrees)
+    ))
+    .down_from(model.ids.lyapunov, 10.0)
+    .w_h(300.0, 20.0)
+    .set(model.ids.relative_angle, ui);
+
+    // Space for the phase portraits, which `ui_view` draws over these so they scroll along
+    let plot = [PHASE_PLOT_SIZE as f64; 2];
+    widget::Rectangle::fill_with(plot, color::TRANSPARENT)
+        .top_left_with_margins_on(model.ids.controls, 10.0, CONTROLS_WIDTH as f64)
+        .set(model.ids.phase1_plot, ui);
+    widget::Rectangle::fill_with(plot, color::TRANSPARENT)
+        .down_from(model.ids.phase1_plot, 30.0)
+        .set(model.ids.phase2_plot, ui);
+
+    // Colors, in the column under the phase portraits
+    let ids = &model.ids;
+    let rows = [
+        (
+            "Arm 1",
+            ids.arm1_color_label,
+            [ids.arm1_r, ids.arm1_g, ids.arm1_b],
+        ),
+        (
+            "Arm 2",
+            ids.arm2_color_label,
+            [ids.arm2_r, ids.arm2_g, ids.arm2_b],
+        ),
+        (
+            "Bob 1",
+            ids.bob1_color_label,
+            [ids.bob1_r, ids.bob1_g, ids.bob1_b],
+        ),
+        (
+            "Bob 2",
+            ids.bob2_color_label,
+            [ids.bob2_r, ids.bob2_g, ids.bob2_b],
+        ),
+    ];
+    let colors = [
+        &mut model.arm1_color,
+        &mut model.arm2_color,
+        &mut model.bob1_color,
+        &mut model.bob2_color,
+    ];
+    let mut above = ids.phase2_plot;
+    for ((name, label, channels), color) in rows.into_iter().zip(colors) {
+        color_sliders(ui, name, label, channels, above, color);
+        above = label;
+    }
+
+    // And the separation plot under them
+    widget::Rectangle::fill_with(
+        [PHASE_PLOT_SIZE as f64, SEPARATION_PLOT_HEIGHT as f64],
+        color::TRANSPARENT,
+    )
+    .down_from(above, 45.0)
+    .set(ids.separation_plot, ui);
+
+    widget::Scrollbar::y_axis(ids.controls)
+        .auto_hide(true)
+        .set(ids.controls_scrollbar, ui);
+
+    drop(ui_cell);
+    let changes = timeline::changes(model.state.t, &before, &model.system);
+    model.change_log.extend(changes);
+    if reset {
+        model.reset();
+    }
+    if let Some(len_scale) = len_scale {
+        model.set_len_scale(len_scale);
+    }
+    if let Some(seed) = seed {
+        model.randomize(seed);
+    }
+}
+
+/// Text for the seed box, empty when the configuration didn't come from a seed
+fn format_seed(seed: Option<u64>) -> String {
+    seed.map(|seed| seed.to_string()).unwrap_or_default()
+}
+
+/// A label and red, green and blue sliders for `color`, placed below the widget `above`
+fn color_sliders(
+    ui: &mut UiCell,
+    name: &str,
+    label: widget::Id,
+    channels: [widget::Id; 3],
+    above: widget::Id,
+    color: &mut Srgb,
+) {
+    widget::Text::new(name)
+        .w_h(80.0, 30.0)
+        .down_from(above, 15.0)
+        .set(label, ui);
+
+    let values = [&mut color.red, &mut color.green, &mut color.blue];
+    let tints = [ui::color::RED, ui::color::GREEN, ui::color::BLUE];
+    let mut left = label;
+    for ((id, value), tint) in channels.into_iter().zip(values).zip(tints) {
+        if let Some(v) = widget::Slider::new(*value, 0.0, 1.0)
+            .enabled(true)
+            .right_from(left, 5.0)
+            .w_h(60.0, 30.0)
+            .color(tint)
+            .label(&format!("{:.2}", *value))
+            .set(id, ui)
+        {
+            *value = v;
+        }
+        left = id;
+    }
+}
+
+/// An angle in radians as shown in the UI, in degrees if `degrees` is set
+fn angle_to_display(angle: f64, degrees: bool) -> f64 {
+    if degrees {
+        angle.to_degrees()
+    } else {
+        angle
+    }
+}
+
+/// The inverse of `angle_to_display`, back to radians
+fn angle_from_display(value: f64, degrees: bool) -> f64 {
+    if degrees {
+        value.to_radians()
+    } else {
+        value
+    }
+}
+
+/// Suffix for angles shown by `format_angle`
+fn angle_unit(degrees: bool) -> &'static str {
+    if degrees {
+        "°"
+    } else {
+        " rad"
+    }
+}
+
+/// Format an angle in radians for a text box, in degrees if `degrees` is set
+fn format_angle(angle: f64, degrees: bool) -> String {
+    if degrees {
+        format!("{:.1}", angle_to_display(angle, degrees))
+    } else {
+        format!("{:.4}", angle)
+    }
+}
+
+/// The angles and angular velocities of `state` one per line, in degrees and degrees per second
+/// if `degrees` is set or radians otherwise
+fn format_state(state: &DoublePendulumState, degrees: bool) -> String {
+    let state = state.wrapped();
+    let unit = angle_unit(degrees);
+    let [θ1, θ2, ω1, ω2] =
+        [state.θ1, state.θ2, state.ω1, state.ω2].map(|a| angle_to_display(a, degrees));
+    format!(
+        "θ1 = {:8.2}{unit}\nθ2 = {:8.2}{unit}\nω1 = {:8.2}{unit}/s\nω2 = {:8.2}{unit}/s",
+        θ1, θ2, ω1, ω2
+    )
+}
+
+/// Parse the angle typed into a text box, in degrees if `degrees` is set, into `angle` in
+/// radians. On invalid input the text is restored from the previous angle and `false` returned.
+fn enter_angle(text: &mut String, angle: &mut f64, degrees: bool) -> bool {
+    match text.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() => {
+            *angle = angle_from_display(value, degrees);
+            true
+        }
+        _ => {
+            *text = format_angle(*angle, degrees);
+            false
+        }
+    }
+}
+
+/// Height of the control window, as tall as the screen leaves room for. The controls scroll when
+/// that isn't enough to show them all.
+fn controls_height(app: &App) -> u32 {
+    app.primary_monitor()
+        .map(|monitor| {
+            monitor
+                .size()
+                .to_logical::<f64>(monitor.scale_factor())
+                .height
+        })
+        .map_or(CONTROLS_FALLBACK_HEIGHT, |height| {
+            (height - SCREEN_MARGIN).max(CONTROLS_FALLBACK_HEIGHT as f64) as u32
+        })
+}
+
+fn ui_view(app: &App, model: &Model, frame: Frame) {
+    // The phase portraits change every frame, so the widgets under them are redrawn every frame
+    // too
+    model.ui.draw_to_frame(app, &frame).unwrap();
+    let draw = app.draw();
+    // Laid out with the widgets, see `update_ui`
+    let rect_of = |id| {
+        model.ui.rect_of(id).map(|r| {
+            geom::Rect::from_x_y_w_h(r.x() as f32, r.y() as f32, r.w() as f32, r.h() as f32)
+        })
+    };
+    let (Some(plot1), Some(plot2), Some(plot3)) = (
+        rect_of(model.ids.phase1_plot),
+        rect_of(model.ids.phase2_plot),
+        rect_of(model.ids.separation_plot),
+    ) else {
+        return;
+    };
+    if model.system.enabled2 || model.system.pinned {
+        draw_phase(&draw, &model.phase1, plot1, "θ1", "ω1", None);
+    } else {
+        // A lone arm's motion is all in this plane, so show the flow it follows
+        let bounds = single_pendulum_bounds(&model.system, &model.phase1);
+        draw_phase_field(&draw, &model.system, model.state.t, plot1, bounds);
+        draw_phase(&draw, &model.phase1, plot1, "θ1", "ω1", Some(bounds));
+    }
+    draw_phase(&draw, &model.phase2, plot2, "θ2", "ω2", None);
+    draw_separation(&draw, &model.separation, plot3);
+    draw.to_frame(app, &frame).unwrap();
+}
+
+/// Scrolling plot of log10 of the distance to the shadow run against time, in `rect`
+fn draw_separation(draw: &Draw, samples: &VecDeque<(f64, f32)>, rect: geom::Rect) {
+    draw.rect()
+        .xy(rect.xy())
+        .wh(rect.wh())
+        .no_fill()
+        .stroke(GRAY)
+        .stroke_weight(1.);
+    draw.text("log10 |Δ|")
+        .xy(Vec2::new(rect.left() + 40., rect.top() - 10.))
+        .color(GRAY);
+    let (Some(&(t0, _)), Some(&(t1, _))) = (samples.front(), samples.back()) else {
+        return;
+    };
+    draw.text(&format!("{:.1} s", t0))
+        .xy(Vec2::new(rect.left() + 20., rect.bottom() - 10.))
+        .color(GRAY);
+    draw.text(&format!("{:.1} s", t1))
+        .xy(Vec2::new(rect.right() - 20., rect.bottom() - 10.))
+        .color(GRAY);
+    let (min, max) = samples
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), &(_, y)| {
+            (min.min(y), max.max(y))
+        });
+    draw.text(&format!("{:.0}", max))
+        .xy(Vec2::new(rect.right() - 15., rect.top() - 10.))
+        .color(GRAY);
+    draw.text(&format!("{:.0}", min))
+        .xy(Vec2::new(rect.right() - 15., rect.bottom() + 10.))
+        .color(GRAY);
+    let span = ((t1 - t0) as f32).max(1e-3);
+    let range = (max - min).max(1e-3);
+    let inner = rect.pad(5.);
+    draw.polyline().points(samples.iter().map(|&(t, y)| {
+        Vec2::new(
+            inner.left() + (t - t0) as f32 / span * inner.w(),
+            inner.bottom() + (y - min) / range * inner.h(),
+        )
+    }));
+}
+
+/// Phase portrait of `points` (θ across, ω up) in `rect`, scaled to fit the data or to the
+/// `(min, max)` corners given in `bounds`
+fn draw_phase(
+    draw: &Draw,
+    points: &VecDeque<Vec2>,
+    rect: geom::Rect,
+    θ: &str,
+    ω: &str,
+    bounds: Option<(Vec2, Vec2)>,
+) {
+    draw.rect()
+        .xy(rect.xy())
+        .wh(rect.wh())
+        .no_fill()
+        .stroke(GRAY)
+        .stroke_weight(1.);
+    draw.text(θ)
+        .xy(Vec2::new(rect.right() - 15., rect.bottom() + 10.))
+        .color(GRAY);
+    draw.text(ω)
+        .xy(Vec2::new(rect.left() + 15., rect.top() - 10.))
+        .color(GRAY);
+    let Some(&first) = points.front() else {
+        return;
+    };
+    let (min, max) = bounds.unwrap_or_else(|| {
+        points
+            .iter()
+            .fold((first, first), |(min, max), &p| (min.min(p), max.max(p)))
+    });
+    // Keep a flat range (e.g. the second arm disabled) from dividing by zero
+    let range = (max - min).max(Vec2::splat(1e-3));
+    let inner = rect.pad(5.);
+    let len = points.len() as f32;
+    for (i, &p) in points.iter().enumerate() {
+        let uv = (p - min) / range;
+        let pos = Vec2::new(
+            inner.left() + uv.x * inner.w(),
+            inner.bottom() + uv.y * inner.h(),
+        );
+        draw.ellipse()
+            .radius(1.)
+            .xy(pos)
+            .color(srgba(1., 1., 1., (i + 1) as f32 / len));
+    }
+}
+
+/// Corners of the single pendulum's phase portrait: all angles across, and up and down to
+/// whichever reaches further of the separatrix (with some room around it) and the `points`
+fn single_pendulum_bounds(system: &DoublePendulumSystem, points: &VecDeque<Vec2>) -> (Vec2, Vec2) {
+    let peak = separatrix_speed(system, if system.g >= 0. { 0. } else { PI as f64 });
+    let ω_max = points
+        .iter()
+        .fold(1.25 * peak as f32, |max, p| max.max(p.y.abs()))
+        .max(1.);
+    (Vec2::new(-PI, -ω_max), Vec2::new(PI, ω_max))
+}
+
+/// Angular velocity of the inner arm on its own at angle `θ` with just enough energy to creep up
+/// to the unstable equilibrium. Swings inside this separatrix go back and forth, ones outside
+/// it go all the way round (ignoring damping and driving).
+fn separatrix_speed(system: &DoublePendulumSystem, θ: f64) -> f64 {
+    let g = system.g;
+    (2. * (g.abs() + g * θ.cos()) / system.l1).max(0.).sqrt()
+}
+
+/// Arrows of the single pendulum's (θ1, ω1) vector field at simulated time `t` and its
+/// separatrix, in `rect` spanning the `(min, max)` corners of `bounds`. Arrows point along the
+/// flow as it looks in the plot, longer where it's faster.
+fn draw_phase_field(
+    draw: &Draw,
+    system: &DoublePendulumSystem,
+    t: f64,
+    rect: geom::Rect,
+    bounds: (Vec2, Vec2),
+) {
+    let (min, max) = bounds;
+    let inner = rect.pad(5.);
+    let scale = Vec2::new(inner.w(), inner.h()) / (max - min);
+    let to_plot = |p: Vec2| inner.bottom_left() + (p - min) * scale;
+    let field = single_pendulum_field(
+        system,
+        t,
+        min.x as f64..=max.x as f64,
+        min.y as f64..=max.y as f64,
+        FIELD_COLS,
+        FIELD_ROWS,
+    );
+    let flow: Vec<(Vec2, Vec2)> = field
+        .iter()
+        .map(|&[θ, ω, dθ, dω]| {
+            let at = to_plot(Vec2::new(θ as f32, ω as f32));
+            (at, Vec2::new(dθ as f32, dω as f32) * scale)
+        })
+        .collect();
+    let fastest = flow
+        .iter()
+        .fold(f32::EPSILON, |max, (_, d)| max.max(d.length()));
+    let color = srgba(0.4, 0.6, 1., 0.5);
+    for (at, d) in flow {
+        let length = FIELD_ARROW * (d.length() / fastest).sqrt();
+        if length < 1. {
+            continue;
+        }
+        let half = d.normalize() * length / 2.;
+        draw.arrow()
+            .start(at - half)
+            .end(at + half)
+            .weight(1.)
+            .head_length(3.)
+            .head_width(2.)
+            .color(color);
+    }
+    let samples = 100;
+    for sign in [1., -1.] {
+        let points = (0..=samples).map(|i| {
+            let θ = min.x + (max.x - min.x) * i as f32 / samples as f32;
+            let ω = sign * separatrix_speed(system, θ as f64) as f32;
+            to_plot(Vec2::new(θ, ω.clamp(min.y, max.y)))
+        });
+        draw.polyline()
+            .weight(1.)
+            .points(points)
+            .color(srgba(1., 0.8, 0.2, 0.6));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pausing_stops_the_simulation() {
+        assert!(is_running(false, None));
+        assert!(!is_running(true, None));
+        // Positioning a bob holds it still, moving the pivot doesn't
+        assert!(!is_running(false, Some(Drag::Top)));
+        assert!(!is_running(false, Some(Drag::Bottom)));
+        assert!(is_running(false, Some(Drag::Pivot)));
+        assert!(!is_running(true, Some(Drag::Pivot)));
+    }
+
+    #[test]
+    fn fixed_steps_carries_the_remainder_over() {
+        let dt = 0.01;
+        let mut accumulator = 0.;
+        // Frames of uneven length, 0.1 s in all
+        let steps: usize = [0.004, 0.013, 0.0255, 0.0075, 0.05]
+            .into_iter()
+            .map(|delta| fixed_steps(&mut accumulator, delta, 1., dt))
+            .sum();
+        assert_eq!(steps, 10);
+        assert!(accumulator.abs() < 1e-9);
+        // A frame too short for a step is kept for the next one
+        assert_eq!(fixed_steps(&mut accumulator, 0.006, 1., dt), 0);
+        assert_eq!(fixed_steps(&mut accumulator, 0.006, 1., dt), 1);
+        assert!((accumulator - 0.002).abs() < 1e-9);
+        // A long stall is clamped and the excess dropped
+        assert_eq!(fixed_steps(&mut accumulator, 10., 1., dt), MAX_SUBSTEPS);
+        assert_eq!(accumulator, 0.);
+    }
+
+    #[test]
+    fn trail_keeps_only_the_newest_points() {
+        let mut trail = VecDeque::new();
+        for i in 0..5 {
+            push_bounded(&mut trail, i, 3);
+        }
+        assert_eq!(trail, [2, 3, 4]);
+        // Shrinking the capacity drops the surplus on the next push
+        push_bounded(&mut trail, 5, 1);
+        assert_eq!(trail, [5]);
+    }
+
+    #[test]
+    fn speed_colors_run_from_blue_to_red() {
+        let close = |a: Srgb, b: Srgb| {
+            (a.red - b.red).abs() + (a.green - b.green).abs() + (a.blue - b.blue).abs() < 1e-5
+        };
+        assert!(close(speed_to_color(0., 10.), srgb(0., 0., 1.)));
+        assert!(close(speed_to_color(10., 10.), srgb(1., 0., 0.)));
+        // Clamped either side of the scale
+        assert!(close(speed_to_color(50., 10.), srgb(1., 0., 0.)));
+        assert!(close(speed_to_color(-1., 10.), srgb(0., 0., 1.)));
+        // Green half way
+        assert!(close(speed_to_color(5., 10.), srgb(0., 1., 0.)));
+    }
+
+    #[test]
+    fn hit_test_includes_the_edge() {
+        let target = Vec2::new(10., -5.);
+        assert!(hit_test(target, target, 3.));
+        assert!(hit_test(Vec2::new(13., -5.), target, 3.));
+        assert!(!hit_test(Vec2::new(13.1, -5.), target, 3.));
+        assert!(!hit_test(Vec2::new(12.5, -2.5), target, 3.));
+    }
+
+    #[test]
+    fn angle_from_offset_inverts_bob_positions() {
+        let system = DoublePendulumSystem::default();
+        assert_eq!(angle_from_offset(Vec2::new(0., -1.)), 0.);
+        assert!((angle_from_offset(Vec2::new(1., 0.)) - PI as f64 / 2.).abs() < 1e-6);
+        for (θ1, θ2) in [(0.3, -2.), (3., 1.), (-1.5, 0.)] {
+            let state = DoublePendulumState::new(θ1, θ2, 0., 0.);
+            let (top, bottom) = bob_positions(&system, &state, LEN_SCALE);
+            assert!((angle_from_offset(top) - θ1).abs() < 1e-6);
+            assert!((angle_from_offset(bottom) - θ2).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn ensemble_fans_out_in_the_outer_angle() {
+        let state = DoublePendulumState::new(1., 2., 0.5, -0.5);
+        let members = ensemble(state, 3, 1e-4);
+        assert_eq!(members.len(), 3);
+        for (i, member) in members.iter().enumerate() {
+            assert!((member.θ2 - (2. + (i + 1) as f64 * 1e-4)).abs() < 1e-12);
+            assert_eq!((member.θ1, member.ω1, member.ω2), (1., 0.5, -0.5));
+        }
+        assert!(ensemble(state, 0, 1e-4).is_empty());
+    }
+
+    #[test]
+    fn time_scale_speeds_up_the_simulation() {
+        // A second of 60 fps frames, stepping 240 times per simulated second
+        let steps = |time_scale| {
+            let mut accumulator = 0.;
+            (0..60)
+                .map(|_| fixed_steps(&mut accumulator, 1. / 60., time_scale, 1. / 240.))
+                .sum::<usize>()
+        };
+        // Allowing for a step still in the accumulator
+        assert!((239..=240).contains(&steps(1.)));
+        assert!((11..=12).contains(&steps(0.05)));
+        assert!((1199..=1200).contains(&steps(5.)));
+    }
+
+    #[test]
+    fn single_step_moves_on_by_one_fixed_step() {
+        let system = DoublePendulumSystem::default();
+        // Paused at the start of a run, where the slow start splits the step the most
+        let mut state = DoublePendulumState::new(2., 2., 0., 0.);
+        let mut sim_time = 0.;
+        let substeps = ramp_substeps(4, state.t, 1.);
+        assert!(substeps > 4);
+        // The same split `Model::step` makes
+        let dt = FIXED_DT / substeps as f64;
+        for _ in 0..substeps {
+            let next = system.checked_step(state, dt);
+            assert!(take_step(&mut state, &mut sim_time, next, dt));
+        }
+        assert!((state.t - FIXED_DT).abs() < 1e-15, "t = {}", state.t);
+        assert!((sim_time - FIXED_DT).abs() < 1e-15, "counted {}", sim_time);
+    }
+
+    #[test]
+    fn frame_paths_sort_in_order() {
+        assert_eq!(
+            frame_path(1),
+            PathBuf::from(FRAMES_DIR).join("frame_000001.png")
+        );
+        let paths: Vec<_> = [9, 10, 999_999].into_iter().map(frame_path).collect();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+    }
+
+    #[test]
+    fn phase_points_wrap_the_angles() {
+        let tau = 2. * PI as f64;
+        let state = DoublePendulumState::new(0.5 + 3. * tau, -0.5 - tau, 2., -3.);
+        let (p1, p2) = phase_points(&state);
+        assert!((p1 - Vec2::new(0.5, 2.)).length() < 1e-5);
+        assert!((p2 - Vec2::new(-0.5, -3.)).length() < 1e-5);
+        // Just past a half turn comes round to the other side
+        let (p1, _) = phase_points(&DoublePendulumState::new(PI as f64 + 0.1, 0., 0., 0.));
+        assert!((p1.x - (0.1 - PI)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn zoom_keeps_the_point_under_the_cursor() {
+        let (camera, pivot, anchor) = (
+            Vec2::new(30., -10.),
+            Vec2::new(0., 200.),
+            Vec2::new(-120., 45.),
+        );
+        // The point drawn under the anchor before zooming
+        let d = anchor - camera - pivot;
+        for k in [0.5, 1., 1.1, 4.] {
+            let zoomed = zoom_camera(camera, pivot, anchor, k);
+            assert!((zoomed + pivot + d * k - anchor).length() < 1e-3);
+        }
+        // Zooming about the pivot leaves the camera where it was
+        assert!((zoom_camera(camera, pivot, camera + pivot, 2.) - camera).length() < 1e-4);
+    }
+
+    #[test]
+    fn resetting_the_view_undoes_any_zoom() {
+        let pivot = Vec2::new(0., 200.);
+        let mut view = View::default();
+        let mut k = view.zoom_at(2.5 * LEN_SCALE, pivot, Vec2::new(100., -50.));
+        k *= view.zoom_at(0.7 * LEN_SCALE, pivot, Vec2::new(-300., 20.));
+        assert_ne!(view, View::default());
+        k *= view.reset(pivot);
+        assert_eq!(view, View::default());
+        // The trails end up back at their original size
+        assert!((k - 1.).abs() < 1e-5);
+        // The zoom stays within the slider range
+        view.zoom_at(1e9, pivot, pivot);
+        assert_eq!(view.len_scale, MAX_LEN_SCALE);
+    }
+
+    #[test]
+    fn simulated_time_counts_only_good_steps() {
+        let system = DoublePendulumSystem::default();
+        let mut state = DoublePendulumState::new(1., 2., 0., 0.);
+        let mut sim_time = 0.;
+        for _ in 0..10 {
+            let next = system.checked_step(state, 0.1);
+            assert!(take_step(&mut state, &mut sim_time, next, 0.1));
+        }
+        assert!((sim_time - 1.).abs() < 1e-12);
+        assert!((state.t - 1.).abs() < 1e-12);
+        // A diverged step leaves both alone
+        let before = state;
+        assert!(!take_step(&mut state, &mut sim_time, None, 0.1));
+        assert!((sim_time - 1.).abs() < 1e-12);
+        assert_eq!(
+            (state.θ1, state.ω2, state.t),
+            (before.θ1, before.ω2, before.t)
+        );
+    }
+
+    #[test]
+    fn separation_is_plotted_on_a_log_scale() {
+        let state = DoublePendulumState::new(1., 2., 0., 0.);
+        let shadow = DoublePendulumState { ω2: 1e-3, ..state };
+        assert!((log_separation(&state, &shadow) + 3.).abs() < 1e-5);
+        // Identical runs stay finite
+        assert!(log_separation(&state, &state).is_finite());
+    }
+
+    #[test]
+    fn remembered_pose_is_wrapped_and_restarted() {
+        let tau = std::f64::consts::TAU;
+        let moving = DoublePendulumState {
+            t: 12.5,
+            ..DoublePendulumState::new(0.5 + tau, -2., 3., -4.)
+        };
+        let pose = release_pose(&moving);
+        assert!((pose.θ1 - 0.5).abs() < 1e-12);
+        assert_eq!((pose.θ2, pose.ω1, pose.ω2, pose.t), (-2., 0., 0., 0.));
+    }
+
+    #[test]
+    fn random_configurations_stay_in_the_slider_ranges() {
+        let base = DoublePendulumSystem {
+            b: 0.3,
+            ..Default::default()
+        };
+        for seed in 0..200 {
+            let (system, state) = random_configuration(seed, &base);
+            assert!((0.0..=MAX_G).contains(&system.g));
+            for m in [system.m1, system.m2] {
+                assert!((MIN_MASS..=MAX_MASS).contains(&m));
+            }
+            for l in [system.l1, system.l2] {
+                assert!((MIN_LENGTH..=MAX_LENGTH).contains(&l));
+            }
+            for angle in [state.θ1, state.θ2] {
+                assert!(angle.abs() <= MAX_RANDOM_ANGLE);
+            }
+            assert_eq!((state.ω1, state.ω2), (0., 0.));
+            // Everything else comes from the base
+            assert_eq!(system.b, 0.3);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_configuration() {
+        let base = DoublePendulumSystem::default();
+        let parts = |seed| {
+            let (s, state) = random_configuration(seed, &base);
+            [s.g, s.m1, s.m2, s.l1, s.l2, state.θ1, state.θ2]
+        };
+        assert_eq!(parts(42), parts(42));
+        assert_ne!(parts(42), parts(43));
+        // And the seed box shows it back
+        assert_eq!(format_seed(Some(42)), "42");
+        assert_eq!(format_seed(None), "");
+    }
+
+    #[test]
+    fn state_readout_lists_each_value_in_the_chosen_unit() {
+        let state = DoublePendulumState::new(PI as f64 / 2., -0.25, 1., 0.);
+        assert_eq!(
+            format_state(&state, true),
+            "θ1 =    90.00°\nθ2 =   -14.32°\nω1 =    57.30°/s\nω2 =     0.00°/s"
+        );
+        assert_eq!(
+            format_state(&state, false),
+            "θ1 =     1.57 rad\nθ2 =    -0.25 rad\nω1 =     1.00 rad/s\nω2 =     0.00 rad/s"
+        );
+        // Angles are shown wrapped
+        let turned = DoublePendulumState::new(0.25 + std::f64::consts::TAU, 0., 0., 0.);
+        assert!(format_state(&turned, false).starts_with("θ1 =     0.25 rad"));
+    }
+
+    #[test]
+    fn bob_positions_are_scaled_pixel_offsets() {
+        let system = DoublePendulumSystem {
+            l1: 1.,
+            l2: 0.5,
+            ..Default::default()
+        };
+        // Hanging straight down
+        let (top, bottom) = bob_positions(&system, &DoublePendulumState::new(0., 0., 0., 0.), 100.);
+        assert!((top - Vec2::new(0., -100.)).length() < 1e-4);
+        assert!((bottom - Vec2::new(0., -50.)).length() < 1e-4);
+        // Inner arm out to the right, outer arm straight up from it
+        let state = DoublePendulumState::new(PI as f64 / 2., PI as f64, 0., 0.);
+        let (top, bottom) = bob_positions(&system, &state, 200.);
+        assert!((top - Vec2::new(200., 0.)).length() < 1e-3);
+        assert!((bottom - Vec2::new(0., 100.)).length() < 1e-3);
+    }
+
+    #[test]
+    fn angles_round_trip_through_the_display_unit() {
+        for angle in [0., 0.5, -2., 3.1] {
+            for degrees in [true, false] {
+                let shown = angle_to_display(angle, degrees);
+                assert!((angle_from_display(shown, degrees) - angle).abs() < 1e-12);
+            }
+        }
+        assert!((angle_to_display(PI as f64, true) - 180.).abs() < 1e-4);
+        // Typed in degrees, stored in radians
+        let (mut text, mut angle) = ("90".to_string(), 0.);
+        assert!(enter_angle(&mut text, &mut angle, true));
+        assert!((angle - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert_eq!(format_angle(angle, true), "90.0");
+        assert_eq!(format_angle(angle, false), "1.5708");
+        // Bad input puts the text back
+        let mut text = "ninety".to_string();
+        assert!(!enter_angle(&mut text, &mut angle, true));
+        assert_eq!(text, "90.0");
+    }
+
+    #[test]
+    fn every_handled_key_is_in_the_help() {
+        let source = include_str!("main.rs");
+        let start = source.find("\nfn key_pressed(").unwrap();
+        let end = start + source[start..].find("\n}\n").unwrap();
+        let listed: Vec<&str> = KEY_HELP
+            .iter()
+            .flat_map(|&(keys, _)| keys.split(" / "))
+            .map(|key| key.trim_start_matches("Shift+"))
+            .collect();
+        let handled = source[start..end]
+            .split("Key::")
+            .skip(1)
+            .map(|rest| rest.split(|c: char| !c.is_alphanumeric()).next().unwrap());
+        for name in handled {
+            let label = match name {
+                "Period" => ".",
+                "Comma" => ",",
+                "LBracket" => "[",
+                "RBracket" => "]",
+                "Left" | "Right" | "Up" | "Down" => "Arrows",
+                name => name,
+            };
+            assert!(listed.contains(&label), "Key::{} isn't in KEY_HELP", name);
+        }
+    }
+
+    #[test]
+    fn slow_start_ramps_the_substeps_down() {
+        let factor = RAMP_FACTOR as usize;
+        assert_eq!(ramp_substeps(2, 0., 4.), 2 * factor);
+        // Half way through, half the extra substeps
+        assert_eq!(ramp_substeps(2, 2., 4.), 2 + factor - 1);
+        assert_eq!(ramp_substeps(2, 4., 4.), 2);
+        assert_eq!(ramp_substeps(2, 100., 4.), 2);
+        // No slow start at all
+        assert_eq!(ramp_substeps(2, 0., 0.), 2);
+        // Never fewer than the base in between
+        assert!((0..40).all(|i| ramp_substeps(3, i as f64 / 10., 4.) >= 3));
+    }
+
+    #[test]
+    fn trail_hue_turns_with_simulated_time() {
+        assert_eq!(time_to_hue(0., 30.), 0.);
+        assert_eq!(time_to_hue(2., 30.), 60.);
+        // Wrapping round the color wheel, also for times before zero
+        assert_eq!(time_to_hue(13., 30.), 30.);
+        assert_eq!(time_to_hue(-1., 30.), 330.);
+        assert!((0..1000).all(|i| (0.0..360.).contains(&time_to_hue(i as f64 * 0.37, 90.))));
+    }
+
+    #[test]
+    fn bob_size_is_clamped_to_the_window() {
+        let window = geom::Rect::from_w_h(800., 400.);
+        // A unit mass is drawn at the base size, heavier bobs grow with the cube root
+        assert_eq!(mass_to_size(1., 20., window), 20.);
+        assert_eq!(mass_to_size(8., 10., window), 20.);
+        // Never too small to see or grab
+        assert_eq!(mass_to_size(1e-6, 20., window), MIN_BOB_RADIUS);
+        // Never bigger than a tenth of the shorter side
+        assert_eq!(mass_to_size(1000., 20., window), 40.);
+        assert_eq!(
+            mass_to_size(1000., 20., geom::Rect::from_w_h(200., 900.)),
+            20.
+        );
+    }
 }
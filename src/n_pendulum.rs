@@ -0,0 +1,175 @@
+use nalgebra::{vector, DMatrix, DVector, Dyn, OVector, Vector2};
+use ode_solvers::{Rk4, System};
+use std::error::Error;
+use std::fmt;
+
+/// State of a chain of pendulums, angles measured from hanging straight down
+#[derive(Debug, Clone)]
+pub struct NPendulumState {
+    // Link angles, from the pivot outwards
+    pub θ: Vec<f64>,
+    // Link angle changes
+    pub ω: Vec<f64>,
+}
+
+impl NPendulumState {
+    /// Fails unless there is an angular velocity for every angle
+    pub fn new(θ: Vec<f64>, ω: Vec<f64>) -> Result<Self, NPendulumError> {
+        check_lengths(θ.len(), ω.len())?;
+        Ok(Self { θ, ω })
+    }
+
+    fn as_mat(&self) -> OVector<f64, Dyn> {
+        DVector::from_iterator(
+            self.θ.len() * 2,
+            self.θ.iter().chain(self.ω.iter()).copied(),
+        )
+    }
+
+    fn from_mat(mat: &OVector<f64, Dyn>) -> Self {
+        let n = mat.len() / 2;
+        Self {
+            θ: mat.rows(0, n).iter().copied().collect(),
+            ω: mat.rows(n, n).iter().copied().collect(),
+        }
+    }
+}
+
+/// A chain of any number of rigid massless links with a point mass at the end of each. The app
+/// itself only simulates and draws the two link `DoublePendulumSystem`, this is the general case
+/// for use as a library.
+#[derive(Debug, Clone)]
+pub struct NPendulumSystem {
+    // Gravity
+    pub g: f64,
+    /// Weight at the end of each link, from the pivot outwards
+    pub masses: Vec<f64>,
+    /// Length of each link, from the pivot outwards
+    pub lengths: Vec<f64>,
+}
+
+impl NPendulumSystem {
+    /// Fails unless there is a length for every mass
+    pub fn new(g: f64, masses: Vec<f64>, lengths: Vec<f64>) -> Result<Self, NPendulumError> {
+        check_lengths(masses.len(), lengths.len())?;
+        Ok(Self { g, masses, lengths })
+    }
+
+    /// Number of links
+    pub fn len(&self) -> usize {
+        self.masses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masses.is_empty()
+    }
+
+    /// A single RK4 step of `delta`, failing if `state` has the wrong number of links or the
+    /// equations of motion turn singular along the way
+    pub fn step(
+        &self,
+        state: &NPendulumState,
+        delta: f64,
+    ) -> Result<NPendulumState, NPendulumError> {
+        check_lengths(self.len(), state.θ.len())?;
+        let mut solver = Rk4::new(self, 0., state.as_mat(), delta, delta);
+        solver.integrate().map_err(|_| NPendulumError::Singular)?;
+        let out = solver.y_out();
+        let out = &out[out.len() - 1];
+        // Singular accelerations are passed to the solver as NaN
+        if !out.iter().all(|x| x.is_finite()) {
+            return Err(NPendulumError::Singular);
+        }
+        Ok(NPendulumState::from_mat(out))
+    }
+
+    /// Angular accelerations of all links, from the Lagrangian of the chain
+    ///
+    /// Writing μ_ij for the mass at or beyond both links i and j, the equations of motion are
+    /// `M θ'' = f` with `M_ij = μ_ij l_i l_j cos(θi - θj)` and
+    /// `f_i = -Σ_j μ_ij l_i l_j sin(θi - θj) ω_j² - μ_ii g l_i sin θi`. Fails if `state` has
+    /// the wrong number of links or `M` can't be inverted, say because of a zero mass or length.
+    pub fn accelerations(&self, state: &NPendulumState) -> Result<Vec<f64>, NPendulumError> {
+        let n = self.len();
+        check_lengths(n, state.θ.len())?;
+        // Mass at or beyond each link
+        let mut outer_mass = self.masses.clone();
+        for i in (0..n.saturating_sub(1)).rev() {
+            outer_mass[i] += outer_mass[i + 1];
+        }
+        let (θ, ω, l) = (&state.θ, &state.ω, &self.lengths);
+        let mut m = DMatrix::zeros(n, n);
+        let mut f = DVector::zeros(n);
+        for i in 0..n {
+            for j in 0..n {
+                let μ = outer_mass[i.max(j)];
+                let (s, c) = (θ[i] - θ[j]).sin_cos();
+                m[(i, j)] = μ * l[i] * l[j] * c;
+                f[i] -= μ * l[i] * l[j] * s * ω[j] * ω[j];
+            }
+            f[i] -= outer_mass[i] * self.g * l[i] * θ[i].sin();
+        }
+        let accelerations = m.lu().solve(&f).ok_or(NPendulumError::Singular)?;
+        Ok(accelerations.iter().copied().collect())
+    }
+
+    /// Positions of the end of each link relative to the pivot, with x pointing right and y
+    /// pointing up
+    pub fn positions(&self, state: &NPendulumState) -> Vec<Vector2<f64>> {
+        let mut pos = Vector2::zeros();
+        state
+            .θ
+            .iter()
+            .zip(&self.lengths)
+            .map(|(θ, l)| {
+                let (s, c) = θ.sin_cos();
+                pos += vector![l * s, -l * c];
+                pos
+            })
+            .collect()
+    }
+}
+
+impl System<f64, OVector<f64, Dyn>> for &NPendulumSystem {
+    fn system(&self, _t: f64, y: &OVector<f64, Dyn>, dy: &mut OVector<f64, Dyn>) {
+        let n = y.len() / 2;
+        let state = NPendulumState::from_mat(y);
+        let accelerations = self
+            .accelerations(&state)
+            .unwrap_or_else(|_| vec![f64::NAN; n]);
+        for i in 0..n {
+            dy[i] = state.ω[i];
+            dy[n + i] = accelerations[i];
+        }
+    }
+}
+
+/// Why an N-link pendulum can't be set up or stepped
+#[derive(Debug, Clone, PartialEq)]
+pub enum NPendulumError {
+    /// Two lists that need an entry per link have different lengths
+    LengthMismatch { expected: usize, found: usize },
+    /// The equations of motion have no unique solution
+    Singular,
+}
+
+impl fmt::Display for NPendulumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NPendulumError::LengthMismatch { expected, found } => {
+                write!(f, "expected {} links, got {}", expected, found)
+            }
+            NPendulumError::Singular => write!(f, "singular equations of motion"),
+        }
+    }
+}
+
+impl Error for NPendulumError {}
+
+fn check_lengths(expected: usize, found: usize) -> Result<(), NPendulumError> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(NPendulumError::LengthMismatch { expected, found })
+    }
+}
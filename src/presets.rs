@@ -0,0 +1,83 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Named initial conditions worth a look, along with the system they are meant to run in
+pub fn presets() -> Vec<(&'static str, DoublePendulumState, DoublePendulumSystem)> {
+    vec![
+        (
+            "Both horizontal",
+            DoublePendulumState::new(FRAC_PI_2, FRAC_PI_2, 0., 0.),
+            Default::default(),
+        ),
+        (
+            "Inner up / outer down",
+            DoublePendulumState::new(PI - 0.1, 0., 0., 0.),
+            Default::default(),
+        ),
+        (
+            "Near-vertical unstable",
+            DoublePendulumState::new(PI - 1e-3, PI, 0., 0.),
+            Default::default(),
+        ),
+        (
+            "Symmetric 90°",
+            DoublePendulumState::new(FRAC_PI_2, -FRAC_PI_2, 0., 0.),
+            Default::default(),
+        ),
+        (
+            "Heavy outer arm",
+            DoublePendulumState::new(2., 2., 0., 0.),
+            DoublePendulumSystem {
+                m2: 10.,
+                l2: 0.5,
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str) -> (DoublePendulumState, DoublePendulumSystem) {
+        let (_, state, system) = presets().into_iter().find(|&(n, _, _)| n == name).unwrap();
+        (state, system)
+    }
+
+    #[test]
+    fn presets_start_from_their_named_angles() {
+        let angles = |name| {
+            let (state, _) = preset(name);
+            (state.θ1, state.θ2)
+        };
+        assert_eq!(angles("Both horizontal"), (FRAC_PI_2, FRAC_PI_2));
+        assert_eq!(angles("Inner up / outer down"), (PI - 0.1, 0.));
+        assert_eq!(angles("Near-vertical unstable"), (PI - 1e-3, PI));
+        assert_eq!(angles("Symmetric 90°"), (FRAC_PI_2, -FRAC_PI_2));
+        assert_eq!(angles("Heavy outer arm"), (2., 2.));
+        // All released from rest at the start of a run
+        for (name, state, _) in presets() {
+            assert_eq!((state.t, state.ω1, state.ω2), (0., 0., 0.), "{}", name);
+        }
+    }
+
+    #[test]
+    fn only_the_heavy_outer_arm_changes_the_system() {
+        let default = DoublePendulumSystem::default();
+        for (name, _, system) in presets() {
+            let (m2, l2) = if name == "Heavy outer arm" {
+                (10., 0.5)
+            } else {
+                (default.m2, default.l2)
+            };
+            assert_eq!((system.m2, system.l2), (m2, l2), "{}", name);
+            assert_eq!(
+                (system.g, system.m1, system.l1, system.b),
+                (default.g, default.m1, default.l1, default.b),
+                "{}",
+                name
+            );
+        }
+    }
+}
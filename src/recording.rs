@@ -0,0 +1,93 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const HEADER: &str = "t,theta1,theta2,omega1,omega2,tip_x,tip_y";
+const PREFIX: &str = "trajectory_";
+const EXTENSION: &str = ".csv";
+
+/// One sample of a recorded trajectory
+#[derive(Debug, Copy, Clone)]
+pub struct TrajectoryRow {
+    pub t: f64,
+    pub θ1: f64,
+    pub θ2: f64,
+    pub ω1: f64,
+    pub ω2: f64,
+    // Bottom pendulum position relative to the pivot, in meters
+    pub tip_x: f64,
+    pub tip_y: f64,
+}
+
+impl TrajectoryRow {
+    pub fn new(system: &DoublePendulumSystem, state: &DoublePendulumState) -> Self {
+        let (_, tip) = system.bob_positions(state);
+        Self {
+            t: state.t,
+            θ1: state.θ1,
+            θ2: state.θ2,
+            ω1: state.ω1,
+            ω2: state.ω2,
+            tip_x: tip.x,
+            tip_y: tip.y,
+        }
+    }
+
+    /// Format the row as a line of CSV matching `HEADER`, without the line break
+    pub fn to_csv(self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.t, self.θ1, self.θ2, self.ω1, self.ω2, self.tip_x, self.tip_y
+        )
+    }
+}
+
+/// Path in the working directory for a trajectory recorded now
+pub fn timestamped_path() -> PathBuf {
+    PathBuf::from(format!("{}{}{}", PREFIX, crate::unix_time(), EXTENSION))
+}
+
+/// The most recent trajectory written by `timestamped_path` in the working directory, if any
+pub fn latest_path() -> io::Result<Option<PathBuf>> {
+    crate::latest_stamped_path(PREFIX, EXTENSION)
+}
+
+/// Write the header and all `rows` to a CSV file at `path`
+pub fn write_csv(path: impl AsRef<Path>, rows: &[TrajectoryRow]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", HEADER)?;
+    for row in rows {
+        writeln!(writer, "{}", row.to_csv())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn rows_line_up_with_the_header() {
+        let system = DoublePendulumSystem {
+            l1: 1.,
+            l2: 2.,
+            ..Default::default()
+        };
+        // Both arms straight out to the right
+        let state = DoublePendulumState {
+            t: 1.5,
+            ..DoublePendulumState::new(FRAC_PI_2, FRAC_PI_2, 0.25, -3.)
+        };
+        let row = TrajectoryRow::new(&system, &state);
+        let line = row.to_csv();
+        let fields: Vec<&str> = line.split(',').collect();
+        assert_eq!(fields.len(), HEADER.split(',').count());
+        assert_eq!(fields[0], "1.5");
+        assert_eq!(fields[1], FRAC_PI_2.to_string());
+        assert_eq!(fields[3..5], ["0.25", "-3"]);
+        let tip: Vec<f64> = fields[5..].iter().map(|x| x.parse().unwrap()).collect();
+        assert!((tip[0] - 3.).abs() < 1e-12 && tip[1].abs() < 1e-12);
+    }
+}
@@ -0,0 +1,74 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+const PREFIX: &str = "snapshot_";
+const EXTENSION: &str = ".json";
+
+/// Everything needed to resume a simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub system: DoublePendulumSystem,
+    pub state: DoublePendulumState,
+}
+
+impl Snapshot {
+    /// Write the snapshot to `path` as JSON
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Read a snapshot previously written by `save_to_path`
+    pub fn load_from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+/// Path in the working directory for a snapshot taken now. Stamped to the millisecond, so
+/// snapshots taken in quick succession don't overwrite each other. Older snapshots stamped in
+/// seconds still sort before them.
+pub fn timestamped_path() -> PathBuf {
+    PathBuf::from(format!("{}{}{}", PREFIX, crate::unix_time_ms(), EXTENSION))
+}
+
+/// The most recent snapshot written by `timestamped_path` in the working directory, if any
+pub fn latest_path() -> io::Result<Option<PathBuf>> {
+    crate::latest_stamped_path(PREFIX, EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_survive_a_round_trip() {
+        let snapshot = Snapshot {
+            system: DoublePendulumSystem {
+                g: 3.7,
+                m2: 0.4,
+                l1: 1.3,
+                b: 0.05,
+                ..Default::default()
+            },
+            state: DoublePendulumState::new(2.1, -0.3, 1. / 3., -7.25),
+        };
+        let path = std::env::temp_dir().join(format!("nannou_test_{}.json", std::process::id()));
+        snapshot.save_to_path(&path).unwrap();
+        let loaded = Snapshot::load_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(format!("{:?}", loaded), format!("{:?}", snapshot));
+    }
+
+    #[test]
+    fn snapshots_taken_apart_get_their_own_paths() {
+        let first = timestamped_path();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_ne!(timestamped_path(), first);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use nalgebra::Vector2;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// `count` evenly spaced values from `from` to `to`, both included. A single value is `from`.
+///
+/// ```
+/// # use nannou_test::sweep::sweep_values;
+/// assert_eq!(sweep_values(1., 2., 5), [1., 1.25, 1.5, 1.75, 2.]);
+/// assert_eq!(sweep_values(1., 2., 1), [1.]);
+/// ```
+pub fn sweep_values(from: f64, to: f64, count: usize) -> Vec<f64> {
+    let step = if count > 1 {
+        (to - from) / (count - 1) as f64
+    } else {
+        0.
+    };
+    (0..count).map(|i| from + step * i as f64).collect()
+}
+
+/// Copies of `base` with one parameter, written by `set`, swept over `count` values from `from`
+/// to `to`, each paired with its value
+pub fn configurations(
+    base: &DoublePendulumSystem,
+    set: impl Fn(&mut DoublePendulumSystem, f64),
+    from: f64,
+    to: f64,
+    count: usize,
+) -> Vec<(f64, DoublePendulumSystem)> {
+    sweep_values(from, to, count)
+        .into_iter()
+        .map(|value| {
+            let mut system = base.clone();
+            set(&mut system, value);
+            (value, system)
+        })
+        .collect()
+}
+
+/// Run each of `systems` from `initial` for `duration` seconds in steps of `dt`, returning the
+/// path of the outer bob relative to the pivot for each, from the start to the final state. Runs
+/// in parallel with the `parallel` feature.
+pub fn run_all(
+    systems: &[(f64, DoublePendulumSystem)],
+    initial: DoublePendulumState,
+    dt: f64,
+    duration: f64,
+) -> Vec<Vec<Vector2<f64>>> {
+    let steps = (duration / dt).ceil() as usize;
+    let run = |(_, system): &(f64, DoublePendulumSystem)| {
+        system
+            .iter_states(initial, dt)
+            .take(steps + 1)
+            // Stop drawing a run that blows up rather than filling it with NaN
+            .take_while(DoublePendulumState::is_finite)
+            .map(|state| system.bob_positions(&state).1)
+            .collect()
+    };
+    #[cfg(feature = "parallel")]
+    let systems = systems.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let systems = systems.iter();
+    systems.map(run).collect()
+}
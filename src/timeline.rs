@@ -0,0 +1,283 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem, Solver};
+
+/// A parameter of the system that can be changed live from the control window
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Param {
+    G,
+    M1,
+    M2,
+    L1,
+    L2,
+    B,
+    CDrag,
+    DriveAmp,
+    DriveFreq,
+    OmegaFrame,
+    PinRadius,
+    PinFreq,
+}
+
+impl Param {
+    pub const ALL: [Param; 12] = [
+        Param::G,
+        Param::M1,
+        Param::M2,
+        Param::L1,
+        Param::L2,
+        Param::B,
+        Param::CDrag,
+        Param::DriveAmp,
+        Param::DriveFreq,
+        Param::OmegaFrame,
+        Param::PinRadius,
+        Param::PinFreq,
+    ];
+
+    /// Name of the parameter's field in `DoublePendulumSystem`, as given on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Param::G => "g",
+            Param::M1 => "m1",
+            Param::M2 => "m2",
+            Param::L1 => "l1",
+            Param::L2 => "l2",
+            Param::B => "b",
+            Param::CDrag => "c_drag",
+            Param::DriveAmp => "drive_amp",
+            Param::DriveFreq => "drive_freq",
+            Param::OmegaFrame => "omega_frame",
+            Param::PinRadius => "pin_radius",
+            Param::PinFreq => "pin_freq",
+        }
+    }
+
+    /// The parameter called `name`, see `name`
+    pub fn from_name(name: &str) -> Option<Param> {
+        Param::ALL.into_iter().find(|which| which.name() == name)
+    }
+
+    pub fn get(self, system: &DoublePendulumSystem) -> f64 {
+        match self {
+            Param::G => system.g,
+            Param::M1 => system.m1,
+            Param::M2 => system.m2,
+            Param::L1 => system.l1,
+            Param::L2 => system.l2,
+            Param::B => system.b,
+            Param::CDrag => system.c_drag,
+            Param::DriveAmp => system.drive_amp,
+            Param::DriveFreq => system.drive_freq,
+            Param::OmegaFrame => system.omega_frame,
+            Param::PinRadius => system.pin_radius,
+            Param::PinFreq => system.pin_freq,
+        }
+    }
+
+    pub fn set(self, system: &mut DoublePendulumSystem, value: f64) {
+        let field = match self {
+            Param::G => &mut system.g,
+            Param::M1 => &mut system.m1,
+            Param::M2 => &mut system.m2,
+            Param::L1 => &mut system.l1,
+            Param::L2 => &mut system.l2,
+            Param::B => &mut system.b,
+            Param::CDrag => &mut system.c_drag,
+            Param::DriveAmp => &mut system.drive_amp,
+            Param::DriveFreq => &mut system.drive_freq,
+            Param::OmegaFrame => &mut system.omega_frame,
+            Param::PinRadius => &mut system.pin_radius,
+            Param::PinFreq => &mut system.pin_freq,
+        };
+        *field = value;
+    }
+}
+
+/// Something changed during a run that a replay has to change again to follow it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Change {
+    /// A parameter set to a value
+    Param(Param, f64),
+    /// The second arm switched on or off
+    Enabled2(bool),
+    /// The inner bob pinned to the circle or let go
+    Pinned(bool),
+    Solver(Solver),
+    /// Tolerance of the adaptive solver
+    Tol(f64),
+    EnergyCorrect(bool),
+    /// The bobs dragged to the angles θ1 and θ2 and let go from rest
+    Drag(f64, f64),
+}
+
+impl Change {
+    /// Make the change to `system` and `state`
+    pub fn apply(self, system: &mut DoublePendulumSystem, state: &mut DoublePendulumState) {
+        match self {
+            Change::Param(which, value) => which.set(system, value),
+            Change::Enabled2(on) => system.enabled2 = on,
+            Change::Pinned(on) => system.pinned = on,
+            Change::Solver(solver) => system.solver = solver,
+            Change::Tol(tol) => system.tol = tol,
+            Change::EnergyCorrect(on) => system.energy_correct = on,
+            Change::Drag(θ1, θ2) => {
+                *state = DoublePendulumState {
+                    t: state.t,
+                    ..DoublePendulumState::new(θ1, θ2, 0., 0.)
+                }
+            }
+        }
+    }
+}
+
+/// A change made at simulated time `t`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Event {
+    pub t: f64,
+    pub change: Change,
+}
+
+/// Events logged from `before` to `after`, one for each parameter or setting that changed
+pub fn changes(t: f64, before: &DoublePendulumSystem, after: &DoublePendulumSystem) -> Vec<Event> {
+    let mut changes: Vec<Change> = Param::ALL
+        .into_iter()
+        .filter(|which| which.get(before) != which.get(after))
+        .map(|which| Change::Param(which, which.get(after)))
+        .collect();
+    if before.enabled2 != after.enabled2 {
+        changes.push(Change::Enabled2(after.enabled2));
+    }
+    if before.pinned != after.pinned {
+        changes.push(Change::Pinned(after.pinned));
+    }
+    if before.solver != after.solver {
+        changes.push(Change::Solver(after.solver));
+    }
+    if before.tol != after.tol {
+        changes.push(Change::Tol(after.tol));
+    }
+    if before.energy_correct != after.energy_correct {
+        changes.push(Change::EnergyCorrect(after.energy_correct));
+    }
+    changes
+        .into_iter()
+        .map(|change| Event { t, change })
+        .collect()
+}
+
+/// Plays back a log of changes against a rerun of the simulation
+#[derive(Debug, Clone)]
+pub struct Replay {
+    events: Vec<Event>,
+    // Index of the first event not handed out yet
+    next: usize,
+}
+
+impl Replay {
+    /// Events are played back in time order, changes logged at the same time keep the order
+    /// they were logged in
+    pub fn new(mut events: Vec<Event>) -> Self {
+        events.sort_by(|a, b| a.t.total_cmp(&b.t));
+        Self { events, next: 0 }
+    }
+
+    /// The events that are due by simulated time `t` and haven't been handed out yet
+    pub fn due(&mut self, t: f64) -> &[Event] {
+        let start = self.next;
+        while self.next < self.events.len() && self.events[self.next].t <= t {
+            self.next += 1;
+        }
+        &self.events[start..self.next]
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next == self.events.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(t: f64, value: f64) -> Event {
+        Event {
+            t,
+            change: Change::Param(Param::G, value),
+        }
+    }
+
+    #[test]
+    fn replay_hands_out_events_in_time_order() {
+        let mut replay = Replay::new(vec![
+            event(2., 1.),
+            event(0.5, 2.),
+            event(1., 3.),
+            // Logged at the same time, so kept in this order
+            event(1., 4.),
+            event(1., 5.),
+        ]);
+        assert_eq!(replay.due(0.), []);
+        assert_eq!(replay.due(0.5), [event(0.5, 2.)]);
+        // Nothing is handed out twice
+        assert_eq!(replay.due(0.7), []);
+        assert_eq!(
+            replay.due(1.5),
+            [event(1., 3.), event(1., 4.), event(1., 5.)]
+        );
+        assert!(!replay.is_finished());
+        assert_eq!(replay.due(10.), [event(2., 1.)]);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn settings_are_logged_along_with_parameters() {
+        let before = DoublePendulumSystem::default();
+        let after = DoublePendulumSystem {
+            m1: 3.,
+            enabled2: !before.enabled2,
+            pinned: !before.pinned,
+            solver: Solver::Dopri5,
+            tol: before.tol / 10.,
+            energy_correct: !before.energy_correct,
+            ..before.clone()
+        };
+        let logged: Vec<Change> = changes(4., &before, &after)
+            .into_iter()
+            .map(|event| {
+                assert_eq!(event.t, 4.);
+                event.change
+            })
+            .collect();
+        assert_eq!(
+            logged,
+            [
+                Change::Param(Param::M1, 3.),
+                Change::Enabled2(after.enabled2),
+                Change::Pinned(after.pinned),
+                Change::Solver(Solver::Dopri5),
+                Change::Tol(after.tol),
+                Change::EnergyCorrect(after.energy_correct),
+            ]
+        );
+        // Making the logged changes to a copy of the old parameters catches it up
+        let mut system = before.clone();
+        let mut state = DoublePendulumState::new(0., 0., 0., 0.);
+        for change in logged {
+            change.apply(&mut system, &mut state);
+        }
+        assert!(changes(4., &system, &after).is_empty());
+    }
+
+    #[test]
+    fn drags_move_the_bobs_and_stop_them() {
+        let mut system = DoublePendulumSystem::default();
+        let mut state = DoublePendulumState {
+            t: 3.,
+            ..DoublePendulumState::new(0.1, 0.2, 5., -5.)
+        };
+        Change::Drag(1., -1.).apply(&mut system, &mut state);
+        assert_eq!(
+            (state.t, state.θ1, state.θ2, state.ω1, state.ω2),
+            (3., 1., -1., 0., 0.)
+        );
+    }
+}
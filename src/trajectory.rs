@@ -0,0 +1,100 @@
+use crate::dbl_pendulum::{wrap_angle, DoublePendulumState};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Read a trajectory from a CSV file with a header row, such as one recorded with T
+pub fn read_csv(path: impl AsRef<Path>) -> io::Result<Vec<(f64, DoublePendulumState)>> {
+    parse_csv(BufReader::new(File::open(path)?))
+}
+
+/// Parse a trajectory from CSV with a header row naming at least the `t`, `theta1` and `theta2`
+/// columns, in any order and among any others. The angular velocities are read from `omega1`
+/// and `omega2` if present and taken as zero otherwise. The samples are returned as (time, state),
+/// sorted by time.
+///
+/// ```
+/// # use nannou_test::trajectory::parse_csv;
+/// let csv = "t,theta1,theta2\n0,0.5,0\n0.1,0.4,0.2\n";
+/// let samples = parse_csv(csv.as_bytes()).unwrap();
+/// assert_eq!(samples.len(), 2);
+/// assert_eq!(samples[1].1.θ2, 0.2);
+/// ```
+pub fn parse_csv(reader: impl BufRead) -> io::Result<Vec<(f64, DoublePendulumState)>> {
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or_else(|| invalid("empty file".into()))??;
+    let names: Vec<&str> = header.split(',').map(str::trim).collect();
+    let column = |name: &str| {
+        names
+            .iter()
+            .position(|&n| n == name)
+            .ok_or_else(|| invalid(format!("no {} column", name)))
+    };
+    let (t, θ1, θ2) = (column("t")?, column("theta1")?, column("theta2")?);
+    let (ω1, ω2) = (column("omega1").ok(), column("omega2").ok());
+    let mut samples = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        // Line numbers count from 1 and include the header
+        let field = |column: usize| {
+            fields
+                .get(column)
+                .and_then(|field| field.parse::<f64>().ok())
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| invalid(format!("bad {} on line {}", names[column], i + 2)))
+        };
+        let optional = |column: Option<usize>| column.map_or(Ok(0.), field);
+        let state = DoublePendulumState {
+            t: field(t)?,
+            ..DoublePendulumState::new(field(θ1)?, field(θ2)?, optional(ω1)?, optional(ω2)?)
+        };
+        samples.push((state.t, state));
+    }
+    if samples.is_empty() {
+        return Err(invalid("no samples".into()));
+    }
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(samples)
+}
+
+/// The state at time `t` along `samples` sorted by time, interpolated linearly between the two
+/// samples around it and held at the ends. Angles take the short way round, so trajectories
+/// with angles wrapped into a range don't spin a full turn where they wrap.
+///
+/// ```
+/// # use nannou_test::dbl_pendulum::DoublePendulumState;
+/// # use nannou_test::trajectory::interpolate;
+/// let samples = [(0., DoublePendulumState::new(0., 0., 0., 0.))];
+/// assert_eq!(interpolate(&samples, 5.).unwrap().θ1, 0.);
+/// assert!(interpolate(&[], 0.).is_none());
+/// ```
+pub fn interpolate(samples: &[(f64, DoublePendulumState)], t: f64) -> Option<DoublePendulumState> {
+    let (first, last) = (samples.first()?, samples.last()?);
+    if t <= first.0 {
+        return Some(first.1);
+    }
+    if t >= last.0 {
+        return Some(last.1);
+    }
+    // First sample after t, never the first one
+    let next = samples.partition_point(|&(time, _)| time <= t);
+    let ((t0, a), (t1, b)) = (samples[next - 1], samples[next]);
+    let s = (t - t0) / (t1 - t0);
+    let angle = |a: f64, b: f64| a + wrap_angle(b - a) * s;
+    let lerp = |a: f64, b: f64| a + (b - a) * s;
+    Some(DoublePendulumState {
+        θ1: angle(a.θ1, b.θ1),
+        θ2: angle(a.θ2, b.θ2),
+        ω1: lerp(a.ω1, b.ω1),
+        ω2: lerp(a.ω2, b.ω2),
+        t,
+    })
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
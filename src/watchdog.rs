@@ -0,0 +1,146 @@
+use crate::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+
+/// Warns on stderr when the total energy of an undamped, undriven run drifts away from where it
+/// started, which can only be integration error
+#[derive(Debug, Clone)]
+pub struct EnergyWatchdog {
+    /// Drift that triggers a warning, relative to the depth of the potential well (or the
+    /// baseline energy without gravity)
+    pub threshold: f64,
+    // Energy at the start of the run and the parameters (g, m1, m2, l1, l2, enabled2) it was
+    // measured with
+    baseline: Option<(f64, [f64; 6])>,
+    // Whether this run has already been warned about
+    warned: bool,
+}
+
+impl EnergyWatchdog {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            baseline: None,
+            warned: false,
+        }
+    }
+
+    /// Forget the baseline, the next check takes a new one
+    pub fn rebaseline(&mut self) {
+        self.baseline = None;
+        self.warned = false;
+    }
+
+    /// Compare the energy of `state` against the baseline, returning whether it drifted beyond
+    /// the threshold. Damped, driven or spinning systems aren't checked, and changing any of the
+    /// parameters takes a new baseline.
+    pub fn check(&mut self, system: &DoublePendulumSystem, state: &DoublePendulumState) -> bool {
+        // Spinning the frame only conserves the energy measured in the rotating frame
+        if !system.is_conservative() {
+            self.rebaseline();
+            return false;
+        }
+        let params = [
+            system.g,
+            system.m1,
+            system.m2,
+            system.l1,
+            system.l2,
+            system.enabled2 as u8 as f64,
+        ];
+        let energy = system.total_energy(state);
+        let baseline = match self.baseline {
+            Some((baseline, baseline_params)) if baseline_params == params => baseline,
+            _ => {
+                self.baseline = Some((energy, params));
+                self.warned = false;
+                return false;
+            }
+        };
+        // Without gravity there is no well, fall back to the size of the baseline itself
+        let well = system.potential_energy(&DoublePendulumState::new(0., 0., 0., 0.));
+        let scale = well.abs().max(baseline.abs());
+        if scale == 0. {
+            return false;
+        }
+        let drift = ((energy - baseline) / scale).abs();
+        if drift <= self.threshold {
+            return false;
+        }
+        if !self.warned {
+            eprintln!(
+                "Warning: energy drifted by {:.2}% (from {:.4} to {:.4}) at t = {:.2}",
+                drift * 100.,
+                baseline,
+                energy,
+                state.t
+            );
+            self.warned = true;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbl_pendulum::double_pendulum_deriv;
+
+    /// One explicit Euler step, which steadily pumps energy into an oscillator
+    fn euler_step(
+        system: &DoublePendulumSystem,
+        state: DoublePendulumState,
+        dt: f64,
+    ) -> DoublePendulumState {
+        let (θp1, θp2, ωp1, ωp2) = double_pendulum_deriv(&state, system);
+        DoublePendulumState {
+            t: state.t + dt,
+            ..DoublePendulumState::new(
+                state.θ1 + θp1 * dt,
+                state.θ2 + θp2 * dt,
+                state.ω1 + ωp1 * dt,
+                state.ω2 + ωp2 * dt,
+            )
+        }
+    }
+
+    #[test]
+    fn lossy_integrator_trips_the_watchdog() {
+        let system = DoublePendulumSystem::default();
+        let mut watchdog = EnergyWatchdog::new(0.01);
+        let mut state = DoublePendulumState::new(1., 1., 0., 0.);
+        assert!(!watchdog.check(&system, &state));
+        let tripped = (0..1000).any(|_| {
+            state = euler_step(&system, state, 0.01);
+            watchdog.check(&system, &state)
+        });
+        assert!(tripped, "still quiet at t = {}", state.t);
+    }
+
+    #[test]
+    fn accurate_integrator_keeps_the_watchdog_quiet() {
+        let system = DoublePendulumSystem::default();
+        let mut watchdog = EnergyWatchdog::new(0.01);
+        let mut state = DoublePendulumState::new(1., 1., 0., 0.);
+        for _ in 0..1000 {
+            assert!(
+                !watchdog.check(&system, &state),
+                "tripped at t = {}",
+                state.t
+            );
+            state = system.step(state, 0.01);
+        }
+    }
+
+    #[test]
+    fn damping_and_new_parameters_are_not_drift() {
+        let mut system = DoublePendulumSystem::default();
+        let mut watchdog = EnergyWatchdog::new(0.01);
+        let state = DoublePendulumState::new(1., 1., 0., 0.);
+        assert!(!watchdog.check(&system, &state));
+        // Doubling the mass doubles the energy, but takes a new baseline
+        system.m1 *= 2.;
+        assert!(!watchdog.check(&system, &state));
+        // Damping loses energy on purpose
+        system.b = 0.5;
+        assert!(!watchdog.check(&system, &DoublePendulumState::new(0., 0., 0., 0.)));
+    }
+}
@@ -0,0 +1,49 @@
+use nannou_test::basin::{basin, outer_flips};
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem, Solver};
+use std::f64::consts::{PI, TAU};
+
+#[test]
+fn basin_is_deterministic() {
+    let system = DoublePendulumSystem {
+        b: 1.,
+        ..Default::default()
+    };
+    let (cols, rows) = (4, 3);
+    let first = basin(&system, cols, rows, 0.01, 20.);
+    assert_eq!(first.len(), cols * rows);
+    assert_eq!(first, basin(&system, cols, rows, 0.01, 20.));
+    // Cell by cell in order, whether or not it ran in parallel
+    for (i, &flips) in first.iter().enumerate() {
+        let inner = -PI + TAU * ((i % cols) as f64 + 0.5) / cols as f64;
+        let outer = PI - TAU * ((i / cols) as f64 + 0.5) / rows as f64;
+        let initial = DoublePendulumState::new(inner, outer, 0., 0.);
+        assert_eq!(
+            flips,
+            outer_flips(&system, initial, 0.01, 20.),
+            "cell {}",
+            i
+        );
+    }
+}
+
+#[test]
+fn flips_are_counted_with_the_configured_solver() {
+    let damped = DoublePendulumSystem {
+        b: 0.5,
+        ..Default::default()
+    };
+    // The symplectic solver ignores damping, so the same run never settles
+    let undamped = DoublePendulumSystem {
+        solver: Solver::Symplectic,
+        ..damped.clone()
+    };
+    let spun = DoublePendulumState::new(0., 0., 0., 30.);
+    let settled = outer_flips(&damped, spun, 0.01, 60.);
+    let cut_off = outer_flips(&undamped, spun, 0.01, 60.);
+    assert!(
+        cut_off > settled,
+        "{} flips with symplectic, {} with RK4",
+        cut_off,
+        settled
+    );
+}
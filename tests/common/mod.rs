@@ -0,0 +1,79 @@
+//! Helpers shared by the integration tests
+
+use nannou_test::dbl_pendulum::DoublePendulumState;
+use std::path::PathBuf;
+use std::{env, fs};
+
+/// Set this environment variable to rewrite the golden files from the current code instead of
+/// comparing against them
+pub const UPDATE_ENV: &str = "UPDATE_GOLDENS";
+
+/// Largest difference in t, θ1, θ2, ω1 or ω2 between matching states of `a` and `b`, or `None`
+/// if they aren't the same length
+pub fn max_deviation(a: &[DoublePendulumState], b: &[DoublePendulumState]) -> Option<f64> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let deviation = a
+        .iter()
+        .zip(b)
+        .flat_map(|(a, b)| {
+            [
+                a.t - b.t,
+                a.θ1 - b.θ1,
+                a.θ2 - b.θ2,
+                a.ω1 - b.ω1,
+                a.ω2 - b.ω2,
+            ]
+        })
+        .map(f64::abs)
+        .fold(0., f64::max);
+    Some(deviation)
+}
+
+/// Checked in golden trajectory called `name`
+pub fn golden_path(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        &format!("{}.json", name),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Assert `trajectory` is within `tolerance` of the golden trajectory `name` everywhere, or
+/// rewrite the golden file with it when UPDATE_ENV is set
+pub fn assert_matches_golden(name: &str, trajectory: &[DoublePendulumState], tolerance: f64) {
+    let path = golden_path(name);
+    if env::var_os(UPDATE_ENV).is_some() {
+        let json = serde_json::to_string_pretty(trajectory).unwrap();
+        fs::write(&path, json + "\n").unwrap();
+        return;
+    }
+    let json = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "failed to read {} ({}), set {}=1 to create it",
+            path.display(),
+            e,
+            UPDATE_ENV
+        )
+    });
+    let golden: Vec<DoublePendulumState> = serde_json::from_str(&json).unwrap();
+    match max_deviation(trajectory, &golden) {
+        None => panic!(
+            "{} states but the golden {} has {}",
+            trajectory.len(),
+            path.display(),
+            golden.len()
+        ),
+        Some(deviation) => assert!(
+            deviation <= tolerance,
+            "trajectory deviates from {} by {}, more than {}",
+            path.display(),
+            deviation,
+            tolerance
+        ),
+    }
+}
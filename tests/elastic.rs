@@ -0,0 +1,36 @@
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use nannou_test::elastic_pendulum::{ElasticDoublePendulumState, ElasticDoublePendulumSystem};
+
+#[test]
+fn stiff_springs_approximate_rigid_arms() {
+    let rigid = DoublePendulumSystem::default();
+    // Gravity alone stretches these by m g / k, about 10 µm
+    let elastic = ElasticDoublePendulumSystem::from_rigid(&rigid, 1e6);
+    let mut state = DoublePendulumState::new(0.6, -0.4, 0., 1.);
+    let mut stretchy = ElasticDoublePendulumState::from_rigid(&state, &elastic);
+    let dt = 0.01;
+    // Two seconds of regular (not chaotic) swinging
+    for _ in 0..200 {
+        state = rigid.step(state, dt);
+        stretchy = elastic.step(stretchy, dt);
+        assert!(stretchy.is_finite(), "diverged at t = {}", stretchy.t);
+        assert!(
+            (stretchy.r1 - rigid.l1).abs() < 1e-3 && (stretchy.r2 - rigid.l2).abs() < 1e-3,
+            "springs stretched to {} and {} at t = {}",
+            stretchy.r1,
+            stretchy.r2,
+            stretchy.t
+        );
+        let (inner, outer) = rigid.bob_positions(&state);
+        let (stretched_inner, stretched_outer) = elastic.bob_positions(&stretchy);
+        let deviation = (stretched_inner - inner)
+            .norm()
+            .max((stretched_outer - outer).norm());
+        assert!(
+            deviation < 1e-3,
+            "bobs are {} m from where rigid arms put them at t = {}",
+            deviation,
+            state.t
+        );
+    }
+}
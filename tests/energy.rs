@@ -0,0 +1,232 @@
+use std::f64::consts::FRAC_PI_2;
+
+use nannou_test::dbl_pendulum::{
+    double_pendulum_deriv, DoublePendulumState, DoublePendulumSystem, Solver,
+};
+
+#[test]
+fn default_system_conserves_energy() {
+    let system = DoublePendulumSystem::default();
+    let mut state = DoublePendulumState::new(FRAC_PI_2, FRAC_PI_2, 0., 0.);
+    let initial = system.total_energy(&state);
+    let dt = 1e-3;
+    // Ten seconds of chaotic motion from both arms horizontal
+    for _ in 0..10_000 {
+        state = system.step(state, dt);
+        assert!(state.is_finite(), "diverged at t = {}", state.t);
+        let drift = (system.total_energy(&state) - initial).abs();
+        assert!(
+            drift < 1e-3,
+            "energy drifted by {} at t = {}",
+            drift,
+            state.t
+        );
+    }
+}
+
+#[test]
+fn hanging_state_is_a_fixed_point() {
+    let system = DoublePendulumSystem::default();
+    let hanging = DoublePendulumState::new(0., 0., 0., 0.);
+    assert_eq!(double_pendulum_deriv(&hanging, &system), (0., 0., 0., 0.));
+    let later = system.step(hanging, 1.);
+    assert_eq!((later.θ1, later.θ2, later.ω1, later.ω2), (0., 0., 0., 0.));
+}
+
+#[test]
+fn zero_gravity_conserves_kinetic_energy_and_angular_momentum() {
+    // Without gravity nothing exerts a torque about the pivot, so the velocity coupling terms
+    // alone have to keep both the (purely kinetic) energy and the angular momentum constant
+    let system = DoublePendulumSystem {
+        g: 0.,
+        m2: 0.7,
+        l2: 1.3,
+        ..Default::default()
+    };
+    let mut state = DoublePendulumState::new(0.3, 2.1, 1.5, -4.);
+    let energy = system.kinetic_energy(&state);
+    let momentum = system.angular_momentum(&state);
+    for _ in 0..10_000 {
+        state = system.step(state, 1e-3);
+        assert_eq!(system.potential_energy(&state), 0.);
+        let drift = (system.kinetic_energy(&state) - energy).abs() / energy;
+        assert!(
+            drift < 1e-9,
+            "kinetic energy drifted by {} at t = {}",
+            drift,
+            state.t
+        );
+        let drift = (system.angular_momentum(&state) - momentum).abs() / momentum.abs();
+        assert!(
+            drift < 1e-9,
+            "angular momentum drifted by {} at t = {}",
+            drift,
+            state.t
+        );
+    }
+}
+
+#[test]
+fn substepping_reduces_energy_drift() {
+    let system = DoublePendulumSystem::default();
+    let initial = DoublePendulumState::new(2., 2., 0., 0.);
+    let energy = system.total_energy(&initial);
+    let drift = |substeps| {
+        let trajectory = system.simulate_substepped(initial, 0.05, 200, substeps);
+        assert_eq!(trajectory.len(), 201);
+        trajectory
+            .iter()
+            .map(|state| (system.total_energy(state) - energy).abs())
+            .fold(0., f64::max)
+    };
+    // RK4 already splits 0.05 s into steps of 1/240 s, so 32 substeps make them 2.7 times
+    // shorter and the drift about 50 times smaller
+    let (coarse, fine) = (drift(1), drift(32));
+    assert!(
+        fine < coarse / 10.,
+        "32 substeps drifted by {}, 1 by {}",
+        fine,
+        coarse
+    );
+    // No substeps at all is the same as one
+    assert_eq!(drift(0), coarse);
+}
+
+#[test]
+fn adaptive_solver_conserves_energy_to_its_tolerance() {
+    let system = DoublePendulumSystem::default();
+    // Both arms released together from horizontal
+    let initial = DoublePendulumState::new(FRAC_PI_2, FRAC_PI_2, 0., 0.);
+    let energy = system.total_energy(&initial);
+    // The tolerance bounds the error of each internal step relative to the size of the state, so
+    // measure the drift relative to the energy released above hanging at rest. The step errors
+    // mostly cancel over the run, leaving a drift of a few times the tolerance.
+    let hanging = system.total_energy(&DoublePendulumState::new(0., 0., 0., 0.));
+    for tol in [1e-6, 1e-8, 1e-10] {
+        // Ten seconds in a single call, so the solver picks every step itself
+        let end = system.step_adaptive(initial, 10., tol);
+        assert_eq!(end.t, 10.);
+        let drift = (system.total_energy(&end) - energy).abs() / (energy - hanging);
+        assert!(
+            drift < 10. * tol,
+            "energy drifted by {} over 10 s with tolerance {}",
+            drift,
+            tol
+        );
+    }
+}
+
+#[test]
+fn symplectic_energy_error_stays_bounded() {
+    // Short arms swing fast enough for RK4's energy loss to build up at its own step size
+    let system = DoublePendulumSystem {
+        l1: 0.2,
+        l2: 0.2,
+        ..Default::default()
+    };
+    let initial = DoublePendulumState::new(2., 2., 0., 0.);
+    let energy = system.total_energy(&initial);
+    // Relative to the energy above hanging at rest, which doesn't depend on where the potential
+    // energy is measured from
+    let hanging = system.total_energy(&DoublePendulumState::new(0., 0., 0., 0.));
+    let drift = |state: &DoublePendulumState| {
+        (system.total_energy(state) - energy).abs() / (energy - hanging)
+    };
+    let dt = 1. / 240.;
+    let (mut rk4, mut symplectic) = (initial, initial);
+    // A minute of chaotic motion
+    for _ in 0..60 * 240 {
+        rk4 = system.step(rk4, dt);
+        symplectic = system.step_symplectic(symplectic, dt, 1);
+        assert!(
+            drift(&symplectic) < 0.01,
+            "energy drifted by {} at t = {}",
+            drift(&symplectic),
+            symplectic.t
+        );
+    }
+    // The symplectic error goes up and down, RK4's only builds up
+    assert!(
+        drift(&rk4) > 10. * drift(&symplectic),
+        "RK4 drifted by {}, symplectic by {}",
+        drift(&rk4),
+        drift(&symplectic)
+    );
+}
+
+#[test]
+fn damping_only_ever_loses_energy() {
+    let system = DoublePendulumSystem {
+        b: 0.3,
+        ..Default::default()
+    };
+    let mut state = DoublePendulumState::new(2., 2., 0., 0.);
+    let mut energy = system.total_energy(&state);
+    let initial = energy;
+    for _ in 0..1000 {
+        state = system.step(state, 0.01);
+        let next = system.total_energy(&state);
+        // Allowing for RK4's own error while the bobs are nearly still
+        assert!(
+            next <= energy + 1e-9,
+            "energy rose from {} to {} at t = {}",
+            energy,
+            next,
+            state.t
+        );
+        energy = next;
+    }
+    assert!(energy < initial - 1., "only lost {}", initial - energy);
+}
+
+#[test]
+fn restoring_energy_returns_to_the_baseline() {
+    let system = DoublePendulumSystem::default();
+    let state = DoublePendulumState::new(1., -0.5, 2., -3.);
+    let baseline = system.total_energy(&state);
+    // The same angles moving a bit too fast and a bit too slow
+    for k in [1.1, 0.9] {
+        let off = DoublePendulumState {
+            ω1: state.ω1 * k,
+            ω2: state.ω2 * k,
+            ..state
+        };
+        let restored = system.restore_energy(off, baseline);
+        assert!((system.total_energy(&restored) - baseline).abs() < 1e-12);
+        assert_eq!((restored.θ1, restored.θ2), (state.θ1, state.θ2));
+    }
+    // With energy correction on, the run ends where it started
+    let corrected = DoublePendulumSystem {
+        energy_correct: true,
+        ..Default::default()
+    };
+    let start = DoublePendulumState::new(2., 2., 0., 0.);
+    let baseline = corrected.total_energy(&start);
+    let end = corrected.step(start, 10.);
+    assert!((corrected.total_energy(&end) - baseline).abs() < 1e-9);
+}
+
+#[test]
+fn tighter_tolerance_drifts_less() {
+    let initial = DoublePendulumState::new(2., 2., 0., 0.);
+    let drift = |tol| {
+        let system = DoublePendulumSystem {
+            solver: Solver::Dopri5,
+            tol,
+            ..Default::default()
+        };
+        let energy = system.total_energy(&initial);
+        system
+            .simulate(initial, 0.05, 200)
+            .iter()
+            .map(|state| (system.total_energy(state) - energy).abs())
+            .fold(0., f64::max)
+    };
+    let (loose, tight) = (drift(1e-4), drift(1e-10));
+    assert!(
+        tight < loose / 100.,
+        "1e-10 drifted by {}, 1e-4 by {}",
+        tight,
+        loose
+    );
+}
@@ -0,0 +1,48 @@
+use std::f64::consts::{PI, TAU};
+
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+
+#[test]
+fn only_hanging_straight_down_is_stable() {
+    let system = DoublePendulumSystem {
+        m2: 0.4,
+        l2: 1.7,
+        ..Default::default()
+    };
+    let equilibria = system.equilibria();
+    assert_eq!(equilibria.len(), 4);
+    let down_down = DoublePendulumState::new(0., 0., 0., 0.);
+    let up_up = DoublePendulumState::new(PI, PI, 0., 0.);
+    assert!(system.is_stable(&down_down));
+    assert!(!system.is_stable(&up_up));
+    let stable = equilibria.iter().filter(|s| system.is_stable(s)).count();
+    assert_eq!(stable, 1);
+}
+
+#[test]
+fn lone_arm_swings_with_the_small_angle_period() {
+    let system = DoublePendulumSystem {
+        l1: 1.5,
+        enabled2: false,
+        ..Default::default()
+    };
+    let expected = TAU * (system.l1 / system.g).sqrt();
+    let dt = 1e-3;
+    let mut state = DoublePendulumState::new(0.05, 0., 0., 0.);
+    // Released at rest, so it turns round every half period
+    let mut turns = Vec::new();
+    while turns.len() < 5 {
+        let next = system.step(state, dt);
+        if state.ω1.signum() != next.ω1.signum() && state.ω1 != 0. {
+            turns.push(next.t);
+        }
+        state = next;
+    }
+    let period = (turns[4] - turns[0]) / 2.;
+    assert!(
+        (period - expected).abs() < 0.02 * expected,
+        "period {} s, expected {} s",
+        period,
+        expected
+    );
+}
@@ -0,0 +1,86 @@
+use nannou_test::dbl_pendulum::{double_pendulum_deriv, DoublePendulumState, DoublePendulumSystem};
+use std::f64::consts::TAU;
+
+#[test]
+fn weightless_driven_arm_follows_the_drive() {
+    // Without gravity there's no natural frequency, so once the damping has killed the start
+    // up, the arm can only move with the drive
+    let drive_freq = 3.;
+    let system = DoublePendulumSystem {
+        g: 0.,
+        b: 1.,
+        drive_amp: 2.,
+        drive_freq,
+        enabled2: false,
+        ..Default::default()
+    };
+    let dt = 0.01;
+    let mut state = system.step(DoublePendulumState::new(0.5, 0., 0., 0.), 20.);
+    let start = state.t;
+    let mut crossings = Vec::new();
+    for _ in 0..2000 {
+        let next = system.step(state, dt);
+        if state.ω1 < 0. && next.ω1 >= 0. {
+            crossings.push(next.t);
+        }
+        state = next;
+    }
+    let period = TAU / drive_freq;
+    assert_eq!(crossings.len(), ((state.t - start) / period) as usize);
+    for pair in crossings.windows(2) {
+        let gap = pair[1] - pair[0];
+        assert!((gap - period).abs() < 2. * dt, "{} s apart", gap);
+    }
+}
+
+#[test]
+fn air_drag_beats_damping_at_high_speed() {
+    // c ω |ω| > b ω once |ω| > 1 with c = b
+    let coefficient = 0.1;
+    let damped = DoublePendulumSystem {
+        b: coefficient,
+        ..Default::default()
+    };
+    let dragged = DoublePendulumSystem {
+        c_drag: coefficient,
+        ..Default::default()
+    };
+    let start = DoublePendulumState::new(0., 0., 10., -10.);
+    let lost = |system: &DoublePendulumSystem| {
+        system.total_energy(&start) - system.total_energy(&system.step(start, 0.5))
+    };
+    let (damping, drag) = (lost(&damped), lost(&dragged));
+    assert!(damping > 0.);
+    assert!(
+        drag > 2. * damping,
+        "drag lost {}, damping {}",
+        drag,
+        damping
+    );
+}
+
+#[test]
+fn still_frame_is_the_inertial_one() {
+    let inertial = DoublePendulumSystem {
+        m2: 0.6,
+        l2: 1.4,
+        ..Default::default()
+    };
+    let spinning = |omega_frame| DoublePendulumSystem {
+        omega_frame,
+        ..inertial.clone()
+    };
+    for state in [
+        DoublePendulumState::new(0.3, -1.2, 0.5, 2.),
+        DoublePendulumState::new(2., 2., 0., 0.),
+        DoublePendulumState::new(-2.5, 0.1, -3., 1.),
+    ] {
+        let expected = double_pendulum_deriv(&state, &inertial);
+        assert_eq!(double_pendulum_deriv(&state, &spinning(0.)), expected);
+        // The centrifugal terms go with the square of the spin, so they fade out smoothly
+        let (_, _, α1, α2) = double_pendulum_deriv(&state, &spinning(1e-3));
+        assert!((α1 - expected.2).abs() < 1e-5 && (α2 - expected.3).abs() < 1e-5);
+        let (_, _, α1, α2) = double_pendulum_deriv(&state, &spinning(1.));
+        assert!((α1 - expected.2).abs() + (α2 - expected.3).abs() > 1e-3);
+    }
+}
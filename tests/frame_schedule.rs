@@ -0,0 +1,36 @@
+use nannou_test::frame_schedule::FrameSchedule;
+
+#[test]
+fn frames_keep_a_fixed_cadence() {
+    // One second at 10 fps, rendered at an uneven 60 and then 7 fps
+    let mut schedule = FrameSchedule::new(1., 10.);
+    let mut times = Vec::new();
+    let mut elapsed = 0.;
+    while !schedule.is_finished() {
+        let dt = if elapsed < 0.5 { 1. / 60. } else { 1. / 7. };
+        times.extend(schedule.advance(dt));
+        elapsed += dt;
+    }
+    assert!(schedule.advance(0.1).is_none());
+    // While frames render faster than the cadence one lands in each interval, never drifting
+    // ahead of it
+    for (i, t) in times.iter().take_while(|&&t| t < 0.5).enumerate() {
+        let due = i as f64 / 10.;
+        assert!(
+            *t >= due - 1e-9 && *t < due + 1. / 60. + 1e-9,
+            "frame {} captured at {}",
+            i,
+            t
+        );
+    }
+    // Slow frames capture once each and skip the ones they missed instead of bunching up, so no
+    // two frames fall in the same interval
+    for pair in times.windows(2) {
+        assert!(
+            (pair[1] * 10.).floor() > (pair[0] * 10.).floor(),
+            "frames at {:?}",
+            pair
+        );
+    }
+    assert!(times.len() < 10, "{} frames captured", times.len());
+}
@@ -0,0 +1,54 @@
+//! Trajectories compared against checked in goldens, to catch accidental changes to the
+//! physics. After an intended change, regenerate them with `UPDATE_GOLDENS=1 cargo test`.
+#![cfg(feature = "serde")]
+
+mod common;
+
+use common::{assert_matches_golden, max_deviation};
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+
+/// Allowed deviation from a golden, loose enough for floating point differences between
+/// platforms over a couple of seconds of chaos
+const TOLERANCE: f64 = 1e-9;
+
+#[test]
+fn default_swing_matches_golden() {
+    let system = DoublePendulumSystem::default();
+    let trajectory = system.simulate(DoublePendulumState::new(2., 2., 0., 0.), 0.02, 100);
+    assert_matches_golden("default_swing", &trajectory, TOLERANCE);
+}
+
+#[test]
+fn damped_driven_swing_matches_golden() {
+    let system = DoublePendulumSystem {
+        m2: 0.5,
+        l2: 1.5,
+        b: 0.2,
+        drive_amp: 3.,
+        drive_freq: 2.,
+        ..Default::default()
+    };
+    let trajectory = system.simulate(DoublePendulumState::new(1., -0.5, 0., 1.), 0.02, 100);
+    assert_matches_golden("damped_driven_swing", &trajectory, TOLERANCE);
+}
+
+#[test]
+fn deviation_is_the_largest_component_difference() {
+    let a = [
+        DoublePendulumState::new(0., 0., 0., 0.),
+        DoublePendulumState::new(1., 2., 3., 4.),
+    ];
+    let mut b = a;
+    assert_eq!(max_deviation(&a, &b), Some(0.));
+    b[1].ω1 += 0.5;
+    b[0].θ2 -= 0.25;
+    assert_eq!(max_deviation(&a, &b), Some(0.5));
+    b[1].t = -2.;
+    assert_eq!(max_deviation(&a, &b), Some(2.));
+}
+
+#[test]
+fn trajectories_of_different_lengths_dont_match() {
+    let a = [DoublePendulumState::new(0., 0., 0., 0.); 3];
+    assert_eq!(max_deviation(&a, &a[..2]), None);
+}
@@ -0,0 +1,34 @@
+use std::f64::consts::PI;
+
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+
+#[test]
+fn flipping_gravity_stabilizes_up_up() {
+    let system = DoublePendulumSystem {
+        g: -9.8,
+        ..Default::default()
+    };
+    assert!(system.is_stable(&DoublePendulumState::new(PI, PI, 0., 0.)));
+    assert!(!system.is_stable(&DoublePendulumState::new(0., 0., 0., 0.)));
+}
+
+#[test]
+fn flipped_gravity_swings_about_the_top() {
+    let system = DoublePendulumSystem {
+        g: -9.8,
+        ..Default::default()
+    };
+    // Nudged off balancing straight up, which is now the bottom of the well
+    let mut state = DoublePendulumState::new(PI + 0.05, PI - 0.05, 0., 0.);
+    for _ in 0..10_000 {
+        state = system.step(state, 1e-3);
+        assert!(
+            (state.θ1 - PI).abs() < 0.2 && (state.θ2 - PI).abs() < 0.2,
+            "fell to ({}, {}) with ω1 = {} at t = {}",
+            state.θ1,
+            state.θ2,
+            state.ω1,
+            state.t
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use nannou_test::dbl_pendulum::{double_pendulum_deriv, DoublePendulumState, DoublePendulumSystem};
+use nannou_test::n_pendulum::{NPendulumError, NPendulumState, NPendulumSystem};
+
+const TOLERANCE: f64 = 1e-9;
+
+fn two_links(system: &DoublePendulumSystem) -> NPendulumSystem {
+    NPendulumSystem::new(
+        system.g,
+        vec![system.m1, system.m2],
+        vec![system.l1, system.l2],
+    )
+    .unwrap()
+}
+
+fn to_n(state: &DoublePendulumState) -> NPendulumState {
+    NPendulumState::new(vec![state.θ1, state.θ2], vec![state.ω1, state.ω2]).unwrap()
+}
+
+#[test]
+fn two_links_match_the_double_pendulum() {
+    let system = DoublePendulumSystem {
+        m1: 1.5,
+        m2: 0.7,
+        l1: 1.2,
+        l2: 0.8,
+        ..Default::default()
+    };
+    let chain = two_links(&system);
+    let mut state = DoublePendulumState::new(2., -1., 0.5, 3.);
+    let mut links = to_n(&state);
+
+    let (_, _, α1, α2) = double_pendulum_deriv(&state, &system);
+    let accelerations = chain.accelerations(&links).unwrap();
+    assert!((accelerations[0] - α1).abs() < TOLERANCE);
+    assert!((accelerations[1] - α2).abs() < TOLERANCE);
+
+    // Single RK4 steps of the same size on the same equations
+    let dt = 1. / 240.;
+    for _ in 0..240 {
+        state = system.step(state, dt);
+        links = chain.step(&links, dt).unwrap();
+    }
+    let expected = to_n(&state);
+    for (a, b) in links
+        .θ
+        .iter()
+        .chain(&links.ω)
+        .zip(expected.θ.iter().chain(&expected.ω))
+    {
+        assert!((a - b).abs() < TOLERANCE, "{} != {}", a, b);
+    }
+
+    let positions = chain.positions(&links);
+    let (inner, outer) = system.bob_positions(&state);
+    assert!((positions[0] - inner).norm() < TOLERANCE);
+    assert!((positions[1] - outer).norm() < TOLERANCE);
+}
+
+#[test]
+fn mismatched_lists_are_rejected() {
+    assert_eq!(
+        NPendulumSystem::new(9.81, vec![1., 1.], vec![1.]).unwrap_err(),
+        NPendulumError::LengthMismatch {
+            expected: 2,
+            found: 1
+        }
+    );
+    assert!(NPendulumState::new(vec![0.], vec![]).is_err());
+    let chain = NPendulumSystem::new(9.81, vec![1., 1.], vec![1., 1.]).unwrap();
+    let state = NPendulumState::new(vec![0.], vec![0.]).unwrap();
+    assert!(chain.accelerations(&state).is_err());
+    assert!(chain.step(&state, 0.01).is_err());
+}
+
+#[test]
+fn massless_chain_is_singular() {
+    let chain = NPendulumSystem::new(9.81, vec![0., 0.], vec![1., 1.]).unwrap();
+    let state = NPendulumState::new(vec![0.5, 0.], vec![0., 0.]).unwrap();
+    assert_eq!(chain.accelerations(&state), Err(NPendulumError::Singular));
+    assert_eq!(
+        chain.step(&state, 0.01).unwrap_err(),
+        NPendulumError::Singular
+    );
+}
@@ -0,0 +1,51 @@
+//! Only built with the `parallel` feature, comparing the parallel code paths against doing the
+//! same work one at a time
+#![cfg(feature = "parallel")]
+
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use nannou_test::sweep::{configurations, run_all};
+
+fn ensemble() -> Vec<DoublePendulumState> {
+    let base = DoublePendulumState::new(2., 2., 0., 0.);
+    (0..64).map(|seed| base.perturbed(seed, 1e-3)).collect()
+}
+
+#[test]
+fn parallel_ensemble_matches_sequential() {
+    let system = DoublePendulumSystem::default();
+    let mut parallel = ensemble();
+    let mut sequential = ensemble();
+    for _ in 0..100 {
+        system.step_all(&mut parallel, 0.01);
+        for state in &mut sequential {
+            *state = system.checked_step(*state, 0.01).unwrap_or(*state);
+        }
+    }
+    for (a, b) in parallel.iter().zip(&sequential) {
+        assert_eq!([a.θ1, a.θ2, a.ω1, a.ω2, a.t], [b.θ1, b.θ2, b.ω1, b.ω2, b.t]);
+    }
+}
+
+#[test]
+fn parallel_sweep_matches_sequential() {
+    let systems = configurations(
+        &DoublePendulumSystem::default(),
+        |system, m2| system.m2 = m2,
+        0.5,
+        2.,
+        16,
+    );
+    let initial = DoublePendulumState::new(2., 2., 0., 0.);
+    let parallel = run_all(&systems, initial, 0.01, 1.);
+    let sequential: Vec<Vec<_>> = systems
+        .iter()
+        .map(|(_, system)| {
+            system
+                .iter_states(initial, 0.01)
+                .take(101)
+                .map(|state| system.bob_positions(&state).1)
+                .collect()
+        })
+        .collect();
+    assert_eq!(parallel, sequential);
+}
@@ -0,0 +1,23 @@
+use std::f64::consts::PI;
+
+use nannou_test::dbl_pendulum::{single_pendulum_field, DoublePendulumSystem};
+
+#[test]
+fn field_vanishes_at_the_stable_equilibrium() {
+    let system = DoublePendulumSystem::default();
+    // Odd sizes put a sample right on (0, 0)
+    let field = single_pendulum_field(&system, 0., -PI..=PI, -8.0..=8.0, 21, 15);
+    assert_eq!(field.len(), 21 * 15);
+    let [_, _, dθ, dω] = field
+        .iter()
+        .copied()
+        .find(|[θ, ω, _, _]| θ.abs() < 1e-12 && ω.abs() < 1e-12)
+        .expect("no sample at the equilibrium");
+    assert!(dθ.hypot(dω) < 1e-12, "field is ({}, {}) there", dθ, dω);
+    // Everywhere else on the ω = 0 row away from the ends, gravity pulls back towards it
+    for [θ, ω, _, dω] in &field {
+        if *ω == 0. && θ.abs() > 1e-12 && θ.abs() < PI - 1e-12 {
+            assert!(dω * θ < 0., "field at θ = {} points away: {}", θ, dω);
+        }
+    }
+}
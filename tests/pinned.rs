@@ -0,0 +1,34 @@
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+
+#[test]
+fn zero_pin_radius_leaves_a_simple_pendulum() {
+    // Driving the inner bob around a circle of no size holds it on the pivot, so the outer arm
+    // swings like a simple pendulum of its own length
+    let pinned = DoublePendulumSystem {
+        pinned: true,
+        pin_radius: 0.,
+        pin_freq: 5.,
+        l2: 1.3,
+        ..Default::default()
+    };
+    let simple = DoublePendulumSystem {
+        enabled2: false,
+        l1: 1.3,
+        ..Default::default()
+    };
+    let mut outer = DoublePendulumState::new(0., 2.5, 0., -1.);
+    let mut reference = DoublePendulumState::new(2.5, 0., -1., 0.);
+    for _ in 0..2_000 {
+        outer = pinned.step(outer, 1e-3);
+        reference = simple.step(reference, 1e-3);
+        let deviation = (outer.θ2 - reference.θ1)
+            .abs()
+            .max((outer.ω2 - reference.ω1).abs());
+        assert!(
+            deviation < 1e-12,
+            "outer arm deviates by {} at t = {}",
+            deviation,
+            outer.t
+        );
+    }
+}
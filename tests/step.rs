@@ -0,0 +1,76 @@
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem, Solver};
+
+#[test]
+fn adaptive_solver_giving_up_is_reported_as_divergence() {
+    let system = DoublePendulumSystem {
+        solver: Solver::Dopri5,
+        ..Default::default()
+    };
+    let mut states = [DoublePendulumState::new(1., 2., 0., 0.)];
+    // Far more internal steps than the solver allows, which used to panic
+    assert!(system.checked_step(states[0], 1e9).is_none());
+    // The ensemble keeps the last good state instead
+    system.step_all(&mut states, 1e9);
+    let state = states[0];
+    assert_eq!((state.θ1, state.θ2, state.ω1, state.t), (1., 2., 0., 0.));
+}
+
+#[test]
+fn one_long_step_matches_many_short_ones() {
+    let system = DoublePendulumSystem::default();
+    let start = DoublePendulumState::new(1., 2., 0., 0.);
+    let long = system.step(start, 1.);
+    let short = (0..10).fold(start, |state, _| system.step(state, 0.1));
+    assert!((long.t - short.t).abs() < 1e-12);
+    for (a, b) in [
+        (long.θ1, short.θ1),
+        (long.θ2, short.θ2),
+        (long.ω1, short.ω1),
+        (long.ω2, short.ω2),
+    ] {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+}
+
+#[test]
+fn linearized_motion_only_holds_for_small_swings() {
+    let system = DoublePendulumSystem::default();
+    // Largest angle error over ten seconds, relative to the release angle
+    let error = |angle: f64| {
+        let start = DoublePendulumState::new(angle, angle, 0., 0.);
+        let (mut exact, mut linear) = (start, start);
+        let mut error: f64 = 0.;
+        for _ in 0..1000 {
+            exact = system.step(exact, 0.01);
+            linear = system.step_linearized(linear, 0.01);
+            error = error
+                .max((exact.θ1 - linear.θ1).abs())
+                .max((exact.θ2 - linear.θ2).abs());
+        }
+        error / angle
+    };
+    let (small, large) = (error(0.01), error(1.));
+    assert!(small < 0.01, "off by {} of the release angle", small);
+    assert!(large > 0.5, "only off by {} of the release angle", large);
+}
+
+#[test]
+fn every_solver_stays_finite() {
+    for solver in Solver::ALL {
+        let system = DoublePendulumSystem {
+            solver,
+            ..Default::default()
+        };
+        let run = system.simulate(DoublePendulumState::new(2., 2., 0., 0.), 1. / 60., 600);
+        assert!(
+            run.iter().all(DoublePendulumState::is_finite),
+            "{} diverged",
+            solver.name()
+        );
+        assert!(
+            (run[600].t - 10.).abs() < 1e-9,
+            "{} lost time",
+            solver.name()
+        );
+    }
+}
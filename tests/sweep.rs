@@ -0,0 +1,23 @@
+use nannou_test::dbl_pendulum::{DoublePendulumState, DoublePendulumSystem};
+use nannou_test::sweep::{configurations, run_all};
+
+#[test]
+fn sweep_runs_one_configuration_per_value() {
+    let base = DoublePendulumSystem::default();
+    let systems = configurations(&base, |system, l2| system.l2 = l2, 0.5, 2., 7);
+    assert_eq!(systems.len(), 7);
+    assert_eq!(systems[0].1.l2, 0.5);
+    assert_eq!(systems[6].1.l2, 2.);
+    assert!(systems.iter().all(|(l2, system)| system.l2 == *l2));
+    // Everything else is left as it was
+    assert!(systems.iter().all(|(_, system)| system.l1 == base.l1));
+    let paths = run_all(
+        &systems,
+        DoublePendulumState::new(2., 2., 0., 0.),
+        0.01,
+        0.5,
+    );
+    assert_eq!(paths.len(), 7);
+    // The initial state and fifty steps after it
+    assert!(paths.iter().all(|path| path.len() == 51));
+}
@@ -0,0 +1,22 @@
+use nannou_test::trajectory::{interpolate, parse_csv};
+
+#[test]
+fn playback_interpolates_between_samples() {
+    // Columns in the order T writes them, with the tip position left over
+    let csv = "t,theta1,theta2,omega1,omega2,tip_x,tip_y\n\
+        1,0.5,3.1,2,-1,0,0\n\
+        1.5,1.5,-3.1,4,1,0,0\n";
+    let samples = parse_csv(csv.as_bytes()).unwrap();
+    let state = interpolate(&samples, 1.125).unwrap();
+    assert!((state.t - 1.125).abs() < 1e-12);
+    assert!((state.θ1 - 0.75).abs() < 1e-12, "θ1 = {}", state.θ1);
+    assert!((state.ω1 - 2.5).abs() < 1e-12, "ω1 = {}", state.ω1);
+    assert!((state.ω2 + 0.5).abs() < 1e-12, "ω2 = {}", state.ω2);
+    // θ2 wraps past π between the samples, so it carries on over the top rather than swinging
+    // back through the bottom
+    let over_the_top = 3.1 + (std::f64::consts::TAU - 6.2) / 4.;
+    assert!((state.θ2 - over_the_top).abs() < 1e-12, "θ2 = {}", state.θ2);
+    // Held at the ends
+    let end = interpolate(&samples, 9.).unwrap();
+    assert_eq!((end.t, end.θ1, end.θ2), (1.5, 1.5, -3.1));
+}